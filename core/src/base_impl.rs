@@ -1,57 +1,153 @@
+use crate::{
+    coop::{poll_budget, with_budget},
+    task::{Builder, JoinError, PanicPolicy, Priority, TaskId, TaskInfo},
+    task_local::TaskLocals,
+    time::TimerQueue,
+};
+use futures_core::FusedFuture;
 use slabmap::SlabMap;
 use std::{
     cell::RefCell,
-    future::Future,
-    mem::{replace, swap},
+    collections::VecDeque,
+    fmt,
+    future::{poll_fn, Future},
+    mem::{replace, swap, take},
     ops::ControlFlow,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
     pin::{pin, Pin},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
     },
     task::{Context, Poll, Wake, Waker},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
 };
 
 const ID_NULL: usize = usize::MAX;
 const ID_MAIN: usize = usize::MAX - 1;
 
+/// Platform-specific event loop used by [`run`].
 pub trait EventLoop {
+    /// Returns the waker that causes `poll` passed to [`EventLoop::run`] to be called.
     fn waker(&self) -> Waker;
+
+    /// Run the event loop until `poll` returns [`ControlFlow::Break`].
+    ///
+    /// `poll` must be called when the waker returned by [`EventLoop::waker`] is woken,
+    /// and when the deadline passed to [`EventLoop::set_deadline`] is reached.
     fn run<T>(&self, poll: impl FnMut() -> ControlFlow<T>) -> T;
+
+    /// Set the instant at which `poll` passed to [`EventLoop::run`] must be called next.
+    ///
+    /// Called after each call of `poll` that returns [`ControlFlow::Continue`], with the expiration of the earliest timer created by [`time`](crate::time).
+    /// `None` means that there is no timer, and the event loop may wait until the waker is woken.
+    ///
+    /// The default implementation ignores the deadline, so timers fire only when the waker is woken for another reason.
+    /// Override it to support timers.
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        let _ = deadline;
+    }
 }
 
 /// Execute asynchronous runtime that blocks the current thread.
 ///
 /// If not blocking current thread, use [`enter`] and [`leave`] instead.
 pub fn run<F: Future>(l: &impl EventLoop, future: F) -> F::Output {
+    run_with_priority(l, Priority::Normal, future)
+}
+
+/// Execute asynchronous runtime that blocks the current thread, polling `future` with the specified priority.
+///
+/// See [`Priority`] for details.
+pub fn run_with_priority<F: Future>(
+    l: &impl EventLoop,
+    priority: Priority,
+    future: F,
+) -> F::Output {
+    let options = RunOptions {
+        main_priority: priority,
+        ..RunOptions::default()
+    };
+    run_with(l, options, future)
+}
+
+/// Execute asynchronous runtime that blocks the current thread, with the specified options.
+pub fn run_with<F: Future>(l: &impl EventLoop, options: RunOptions, future: F) -> F::Output {
     let mut runner = Runner::new(l.waker());
+    runner.main_priority = options.main_priority;
     Runtime::enter(&runner.rc);
     runner.rc.push_wake(ID_MAIN);
 
     let mut main = pin!(future);
     let main_wake = TaskWake::new(ID_MAIN, &runner.rc);
     let value = l.run(|| {
-        runner.ready_requests();
-        for id in runner.wakes.drain(..) {
-            if id == ID_MAIN {
-                match main
-                    .as_mut()
-                    .poll(&mut Context::from_waker(&main_wake.waker()))
-                {
-                    Poll::Ready(value) => return ControlFlow::Break(value),
-                    Poll::Pending => {}
-                }
-            } else {
-                run_item(&mut runner.rs[id]);
+        let poll_options = PollOptions {
+            deadline: options.time_budget.map(|d| Instant::now() + d),
+            max_polls: options.max_polls,
+            drain_until_stable: options.drain_until_stable,
+        };
+        runner.poll_with(&poll_options, || {
+            match main
+                .as_mut()
+                .poll(&mut Context::from_waker(&main_wake.waker()))
+            {
+                Poll::Ready(value) => ControlFlow::Break(value),
+                Poll::Pending => ControlFlow::Continue(()),
             }
-        }
-        runner.apply_drops();
+        })?;
+        l.set_deadline(next_deadline());
         ControlFlow::Continue(())
     });
     Runtime::leave();
     value
 }
 
+/// Options for [`run_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    /// Priority of the future passed to [`run_with`].
+    pub main_priority: Priority,
+
+    /// Maximum time spent polling futures each time the event loop calls `poll`.
+    pub time_budget: Option<Duration>,
+
+    /// Maximum number of futures polled each time the event loop calls `poll`.
+    pub max_polls: Option<usize>,
+
+    /// See [`PollOptions::drain_until_stable`].
+    pub drain_until_stable: bool,
+}
+
+/// Options for [`poll_with`].
+///
+/// The default value polls the futures woken up before the call, without limits, like [`poll`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PollOptions {
+    /// Stop polling futures once this instant is reached.
+    pub deadline: Option<Instant>,
+
+    /// Stop polling futures once this number of futures has been polled.
+    pub max_polls: Option<usize>,
+
+    /// Also poll futures woken up during the call, until no woken futures remain or a limit is reached.
+    ///
+    /// Without `deadline` or `max_polls`, a future that always wakes itself makes the call never return.
+    pub drain_until_stable: bool,
+}
+
+/// Result of [`poll_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollResult {
+    /// Number of futures polled.
+    pub polls: usize,
+
+    /// `true` if there are woken futures that have not been polled yet.
+    ///
+    /// The runtime backend's waker has been woken for them, so it is not necessary to reschedule manually unless the host loop wants to poll again immediately.
+    pub remaining: bool,
+}
+
 thread_local! {
     static RUNNER: RefCell<Option<Runner>> = const { RefCell::new(None) };
 }
@@ -78,7 +174,19 @@ pub fn leave() {
 ///
 /// `Future::poll` is not called for futures that were woken up during the call to this function.
 pub fn poll() {
-    call_runner(|r| r.poll())
+    poll_with(PollOptions::default());
+}
+
+/// Call [`poll`](std::future::Future::poll) of futures started by [`spawn_local`] with the specified options.
+///
+/// Returns whether woken futures remain so that the host loop can reschedule.
+pub fn poll_with(options: PollOptions) -> PollResult {
+    call_runner(
+        |r| match r.poll_with(&options, || ControlFlow::<()>::Continue(())) {
+            ControlFlow::Continue(result) => result,
+            ControlFlow::Break(()) => unreachable!(),
+        },
+    )
 }
 
 fn call_runner<T>(f: impl FnOnce(&mut Runner) -> T) -> T {
@@ -96,31 +204,73 @@ pub fn idle() -> bool {
     Runtime::with(|rt| rt.wake_idles())
 }
 
+/// Returns the instant at which the earliest timer created by [`time`](crate::time) expires.
+///
+/// The runtime backend must call [`poll`] when this instant is reached.
+/// [`run`] passes this instant to [`EventLoop::set_deadline`].
+pub fn next_deadline() -> Option<Instant> {
+    Runtime::with(|rt| rt.timers.next_deadline())
+}
+
 /// Spawn a future on the current thread.
 ///
+/// If the future panics, the panic is handled according to [`PanicPolicy`].
+///
 /// # Panics
 ///
 /// Panics if the runtime is not running.
 #[must_use]
 #[track_caller]
 pub fn spawn_local<F: Future + 'static>(future: F) -> Task<F::Output> {
+    Builder::new().spawn_local(future)
+}
+
+/// Spawn a future on the current thread with the specified priority.
+///
+/// See [`Priority`] for details.
+///
+/// # Panics
+///
+/// Panics if the runtime is not running.
+#[must_use]
+#[track_caller]
+pub fn spawn_local_with_priority<F: Future + 'static>(
+    priority: Priority,
+    future: F,
+) -> Task<F::Output> {
+    Builder::new().priority(priority).spawn_local(future)
+}
+
+pub(crate) fn spawn_local_with<F: Future + 'static>(future: F, info: TaskInfo) -> Task<F::Output> {
     Runtime::with(|rt| {
         let need_wake = rt.rs.is_empty();
-        let task = RawTask::new(&rt.rc);
-        rt.rs.push(Box::pin(RawRunnable {
-            task: task.clone(),
-            future,
-        }));
+        let (r, task) = new_task(future, info, &rt.rc);
+        rt.rs.push(r);
         if need_wake {
             rt.rc.0.waker.wake_by_ref();
         }
-        Task {
-            task,
-            is_detach: false,
-        }
+        task
     })
 }
 
+pub(crate) fn new_task<'a, F: Future + 'a>(
+    future: F,
+    info: TaskInfo,
+    rc: &RequestChannel,
+) -> (Pin<Box<dyn DynRunnable + 'a>>, Task<F::Output>) {
+    let task = RawTask::new(rc, info);
+    let r = Box::pin(RawRunnable {
+        task: task.clone(),
+        locals: TaskLocals::inherited(),
+        future: Some(future),
+    });
+    let task = Task {
+        task,
+        is_detach: false,
+    };
+    (r, task)
+}
+
 /// Wait until there are no more operations to be performed now on the current thread.
 ///
 /// The "operations to be performed now" include not only tasks spawned by [`spawn_local`], but also events handled by the runtime backend.
@@ -145,17 +295,42 @@ pub async fn wait_for_idle() {
     WaitForIdle { is_ready: false }.await;
 }
 
+/// Yield execution back to the runtime.
+///
+/// The current task is polled again after the other tasks that have been woken up.
+pub async fn yield_now() {
+    struct YieldNow {
+        is_ready: bool,
+    }
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if self.is_ready {
+                Poll::Ready(())
+            } else {
+                self.is_ready = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    YieldNow { is_ready: false }.await;
+}
+
 #[derive(Clone)]
-struct RequestChannel(Arc<RequestsData>);
+pub(crate) struct RequestChannel(Arc<RequestsData>);
 
 impl RequestChannel {
-    fn new(waker: Waker) -> Self {
+    pub(crate) fn new(waker: Waker) -> Self {
         Self(Arc::new(RequestsData {
             reqs: Mutex::new(RawRequests::new()),
             waker,
+            thread_id: thread::current().id(),
         }))
     }
-    fn get_wakes_drops(&self, wakes: &mut Vec<usize>, drops: &mut Vec<usize>) {
+    pub(crate) fn get_wakes_drops(&self, wakes: &mut Vec<usize>, drops: &mut Vec<usize>) {
         assert!(wakes.is_empty());
         assert!(drops.is_empty());
         let mut reqs = self.0.reqs.lock().unwrap();
@@ -167,6 +342,10 @@ impl RequestChannel {
         swap(idles, &mut self.0.reqs.lock().unwrap().idles);
     }
 
+    fn has_wake(&self, f: impl Fn(usize) -> bool) -> bool {
+        self.0.reqs.lock().unwrap().wakes.iter().any(|&id| f(id))
+    }
+
     fn push_with(&self, f: impl FnOnce(&mut RawRequests)) {
         let mut d = self.0.reqs.lock().unwrap();
         let call_wake = d.is_empty();
@@ -184,10 +363,40 @@ impl RequestChannel {
     fn push_idle_waker(&self, waker: Waker) {
         self.push_with(|d| d.idles.push(waker));
     }
+    pub(crate) fn push_dispatch(&self, f: Dispatch) -> Result<(), Dispatch> {
+        let mut d = self.0.reqs.lock().unwrap();
+        if d.is_closed {
+            return Err(f);
+        }
+        let call_wake = d.is_empty();
+        d.dispatches.push(f);
+        if call_wake {
+            self.0.waker.wake_by_ref();
+        }
+        Ok(())
+    }
+    fn get_dispatches(&self, dispatches: &mut Vec<Dispatch>) {
+        assert!(dispatches.is_empty());
+        swap(dispatches, &mut self.0.reqs.lock().unwrap().dispatches);
+    }
+    fn close(&self) {
+        let dispatches = {
+            let mut d = self.0.reqs.lock().unwrap();
+            d.is_closed = true;
+            take(&mut d.dispatches)
+        };
+        drop(dispatches);
+    }
+    pub(crate) fn is_running_on_current_thread(&self) -> bool {
+        self.0.thread_id == thread::current().id() && !self.0.reqs.lock().unwrap().is_closed
+    }
 }
 
+pub(crate) type Dispatch = Box<dyn FnOnce() + Send>;
+
 struct RequestsData {
     waker: Waker,
+    thread_id: ThreadId,
     reqs: Mutex<RawRequests>,
 }
 
@@ -195,6 +404,8 @@ struct RawRequests {
     wakes: Vec<usize>,
     drops: Vec<usize>,
     idles: Vec<Waker>,
+    dispatches: Vec<Dispatch>,
+    is_closed: bool,
 }
 
 impl RawRequests {
@@ -203,10 +414,15 @@ impl RawRequests {
             wakes: Vec::new(),
             drops: Vec::new(),
             idles: Vec::new(),
+            dispatches: Vec::new(),
+            is_closed: false,
         }
     }
     fn is_empty(&self) -> bool {
-        self.wakes.is_empty() && self.drops.is_empty() && self.idles.is_empty()
+        self.wakes.is_empty()
+            && self.drops.is_empty()
+            && self.idles.is_empty()
+            && self.dispatches.is_empty()
     }
 }
 
@@ -214,10 +430,13 @@ thread_local! {
     static RUNTIME: RefCell<Option<Runtime>> = const { RefCell::new(None) };
 }
 
-struct Runtime {
-    rc: RequestChannel,
+pub(crate) struct Runtime {
+    pub(crate) rc: RequestChannel,
     rs: Vec<Pin<Box<dyn DynRunnable>>>,
     idles: Vec<Waker>,
+    pub(crate) timers: TimerQueue,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) coop_budget: Option<u32>,
 }
 
 impl Runtime {
@@ -226,6 +445,9 @@ impl Runtime {
             rc,
             rs: Vec::new(),
             idles: Vec::new(),
+            timers: TimerQueue::new(),
+            panic_policy: PanicPolicy::default(),
+            coop_budget: None,
         }
     }
     fn enter(rc: &RequestChannel) {
@@ -238,7 +460,9 @@ impl Runtime {
         })
     }
     fn leave() {
-        RUNTIME.with(|rt| rt.borrow_mut().take());
+        if let Some(rt) = RUNTIME.with(|rt| rt.borrow_mut().take()) {
+            rt.rc.close();
+        }
     }
     fn wake_idles(&mut self) -> bool {
         if !self.rs.is_empty() {
@@ -254,11 +478,17 @@ impl Runtime {
         true
     }
     #[track_caller]
-    fn with<T>(f: impl FnOnce(&mut Self) -> T) -> T {
+    pub(crate) fn with<T>(f: impl FnOnce(&mut Self) -> T) -> T {
         RUNTIME
             .with(|rt| rt.borrow_mut().as_mut().map(f))
             .expect("runtime is not running")
     }
+    pub(crate) fn try_with<T>(f: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        RUNTIME
+            .try_with(|rt| rt.try_borrow_mut().ok()?.as_mut().map(f))
+            .ok()
+            .flatten()
+    }
 }
 
 /// A spawned task.
@@ -266,6 +496,10 @@ impl Runtime {
 /// When a [`Task`] is dropped, the asynchronous operation is canceled.
 ///
 /// To drop a task without canceling, it is necessary to call [`Task::detach()`].
+///
+/// If the task panicked, awaiting the [`Task`] resumes the panic. Use [`Task::join()`] to receive the panic as [`JoinError`].
+///
+/// If the task was canceled by [`AbortHandle::abort()`], awaiting the [`Task`] panics.
 pub struct Task<T> {
     task: Arc<RawTask<T>>,
     is_detach: bool,
@@ -274,12 +508,14 @@ pub struct Task<T> {
 struct RawTask<T> {
     state: Mutex<TaskState<T>>,
     reqs: RequestChannel,
+    info: TaskInfo,
 }
 
 enum TaskState<T> {
     Running { id: usize, waker: Option<Waker> },
+    Cancelling { waker: Option<Waker> },
     Cancelled,
-    Completed(T),
+    Completed(Result<T, JoinError>),
     Finished,
 }
 
@@ -288,35 +524,55 @@ impl<T> Task<T> {
     pub fn detach(mut self) {
         self.is_detach = true;
     }
-}
 
-impl<T> Drop for Task<T> {
-    fn drop(&mut self) {
-        if !self.is_detach {
-            let mut state = self.task.state.lock().unwrap();
-            if let &TaskState::Running { id, .. } = &*state {
-                *state = TaskState::Cancelled;
-                if id != ID_NULL {
-                    self.task.reqs.push_wake(id);
-                }
-            }
-        }
+    /// Wait for the task to finish, returning [`JoinError`] instead of resuming the panic if the task panicked.
+    pub fn join(self) -> Join<T> {
+        Join(self)
     }
-}
-impl<T> Future for Task<T> {
-    type Output = T;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    /// Cancel the task and wait until its future is dropped.
+    ///
+    /// Returns the output of the task if it completed before being canceled.
+    /// Returns `None` if the task was canceled or panicked.
+    pub async fn cancel(self) -> Option<T> {
+        self.task.abort();
+        poll_fn(|cx| self.task.poll_cancel(cx)).await
+    }
+
+    /// Returns `true` if the future of the task has completed or has been dropped.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Returns the id of the task.
+    pub fn id(&self) -> TaskId {
+        self.task.info.id
+    }
+
+    /// Returns the name of the task specified by [`Builder::name`].
+    pub fn name(&self) -> Option<&str> {
+        self.task.info.name()
+    }
+
+    /// Returns a handle that can be used to cancel the task without owning it.
+    pub fn abort_handle(&self) -> AbortHandle
+    where
+        T: 'static,
+    {
+        AbortHandle(self.task.clone())
+    }
+
+    fn poll_result(&self, cx: &mut Context<'_>) -> Poll<Result<T, JoinError>> {
         let mut state = self.task.state.lock().unwrap();
-        match &*state {
-            &TaskState::Running { id, .. } => {
-                *state = TaskState::Running {
-                    id,
-                    waker: Some(cx.waker().clone()),
-                };
+        match &mut *state {
+            TaskState::Running { waker, .. } | TaskState::Cancelling { waker } => {
+                *waker = Some(cx.waker().clone());
                 Poll::Pending
             }
-            TaskState::Cancelled => Poll::Pending,
+            TaskState::Cancelled => {
+                *state = TaskState::Finished;
+                Poll::Ready(Err(JoinError::cancelled(self.task.info.clone())))
+            }
             TaskState::Completed(_) => {
                 if let TaskState::Completed(value) = replace(&mut *state, TaskState::Finished) {
                     Poll::Ready(value)
@@ -324,22 +580,129 @@ impl<T> Future for Task<T> {
                     unreachable!()
                 }
             }
-            TaskState::Finished => panic!("`poll` called twice"),
+            TaskState::Finished => Poll::Pending,
         }
     }
+    fn is_terminated(&self) -> bool {
+        matches!(&*self.task.state.lock().unwrap(), TaskState::Finished)
+    }
+}
+
+impl<T> fmt::Debug for Task<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Task")
+            .field("id", &self.task.info.id)
+            .field("name", &self.task.info.name())
+            .field("location", &self.task.info.location)
+            .field("is_finished", &self.is_finished())
+            .finish()
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        if !self.is_detach {
+            self.task.abort();
+        }
+    }
+}
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        poll_budget(cx, |cx| self.poll_result(cx)).map(|r| match r {
+            Ok(value) => value,
+            Err(e) if e.is_panic() => resume_unwind(e.into_panic()),
+            Err(e) => panic!("{e}"),
+        })
+    }
+}
+impl<T> FusedFuture for Task<T> {
+    fn is_terminated(&self) -> bool {
+        Task::is_terminated(self)
+    }
+}
+
+/// Future returned by [`Task::join`].
+///
+/// When a [`Join`] is dropped, the task is canceled.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Join<T>(Task<T>);
+
+impl<T> Join<T> {
+    /// Drop a task without canceling.
+    pub fn detach(self) {
+        self.0.detach()
+    }
+}
+
+impl<T> Future for Join<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        poll_budget(cx, |cx| self.0.poll_result(cx))
+    }
+}
+impl<T> FusedFuture for Join<T> {
+    fn is_terminated(&self) -> bool {
+        self.0.is_terminated()
+    }
+}
+
+/// A handle to cancel a task without owning it, created by [`Task::abort_handle`].
+#[derive(Clone)]
+pub struct AbortHandle(Arc<dyn DynTask>);
+
+impl AbortHandle {
+    /// Cancel the task.
+    ///
+    /// The future of the task is dropped the next time the runtime polls tasks.
+    /// Does nothing if the task has already finished.
+    pub fn abort(&self) {
+        self.0.abort()
+    }
+
+    /// Returns `true` if the future of the task has completed or has been dropped.
+    pub fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    /// Returns the id of the task.
+    pub fn id(&self) -> TaskId {
+        self.0.info().id
+    }
+}
+
+trait DynTask {
+    fn abort(&self);
+    fn is_finished(&self) -> bool;
+    fn info(&self) -> &TaskInfo;
+}
+
+impl<T> DynTask for RawTask<T> {
+    fn abort(&self) {
+        RawTask::abort(self)
+    }
+    fn is_finished(&self) -> bool {
+        RawTask::is_finished(self)
+    }
+    fn info(&self) -> &TaskInfo {
+        &self.info
+    }
 }
 
 impl<T> RawTask<T> {
-    fn new(rc: &RequestChannel) -> Arc<Self> {
+    fn new(rc: &RequestChannel, info: TaskInfo) -> Arc<Self> {
         Arc::new(RawTask {
             state: Mutex::new(TaskState::Running {
                 id: ID_NULL,
                 waker: None,
             }),
             reqs: rc.clone(),
+            info,
         })
     }
-    fn complete(&self, value: T) {
+    fn complete(&self, value: Result<T, JoinError>) {
         if let TaskState::Running {
             waker: Some(waker), ..
         } = replace(
@@ -349,49 +712,129 @@ impl<T> RawTask<T> {
             waker.wake()
         }
     }
-    fn is_cancelled(&self) -> bool {
-        matches!(&*self.state.lock().unwrap(), TaskState::Cancelled)
+    fn abort(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let TaskState::Running { id, waker } = &mut *state {
+            let id = *id;
+            let waker = waker.take();
+            *state = TaskState::Cancelling { waker };
+            if id != ID_NULL {
+                self.reqs.push_wake(id);
+            }
+        }
+    }
+    fn on_drop_future(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let TaskState::Running { waker, .. } | TaskState::Cancelling { waker } = &mut *state {
+            let waker = waker.take();
+            *state = TaskState::Cancelled;
+            drop(state);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+    fn poll_cancel(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.state.lock().unwrap();
+        match &mut *state {
+            TaskState::Running { waker, .. } | TaskState::Cancelling { waker } => {
+                *waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            TaskState::Completed(_) => {
+                if let TaskState::Completed(value) = replace(&mut *state, TaskState::Finished) {
+                    Poll::Ready(value.ok())
+                } else {
+                    unreachable!()
+                }
+            }
+            TaskState::Cancelled | TaskState::Finished => {
+                *state = TaskState::Finished;
+                Poll::Ready(None)
+            }
+        }
+    }
+    fn is_cancelling(&self) -> bool {
+        matches!(&*self.state.lock().unwrap(), TaskState::Cancelling { .. })
+    }
+    fn is_finished(&self) -> bool {
+        !matches!(
+            &*self.state.lock().unwrap(),
+            TaskState::Running { .. } | TaskState::Cancelling { .. }
+        )
     }
 }
 
-trait DynRunnable {
+pub(crate) trait DynRunnable {
+    fn priority(&self) -> Priority;
     fn set_id(self: Pin<&Self>, id: usize);
     fn run(self: Pin<&mut Self>, waker: &Waker) -> bool;
 }
 
 struct RawRunnable<F: Future> {
     task: Arc<RawTask<F::Output>>,
-    future: F,
+    locals: TaskLocals,
+    future: Option<F>,
 }
 impl<Fut: Future> DynRunnable for RawRunnable<Fut> {
+    fn priority(&self) -> Priority {
+        self.task.info.priority
+    }
     fn set_id(self: Pin<&Self>, id: usize) {
         if let TaskState::Running { id: id_, .. } = &mut *self.task.state.lock().unwrap() {
             *id_ = id;
         }
     }
     fn run(self: Pin<&mut Self>, waker: &Waker) -> bool {
-        if self.task.is_cancelled() {
+        if self.task.is_cancelling() {
             false
         } else {
-            unsafe {
-                let this = self.get_unchecked_mut();
-                let f = Pin::new_unchecked(&mut this.future);
-                if let Poll::Ready(value) = f.poll(&mut Context::from_waker(waker)) {
-                    this.task.complete(value);
+            let this = unsafe { self.get_unchecked_mut() };
+            let Some(f) = this.future.as_mut() else {
+                return false;
+            };
+            let f = unsafe { Pin::new_unchecked(f) };
+            let r = this.locals.enter(|| {
+                this.task.info.enter(|| {
+                    catch_unwind(AssertUnwindSafe(|| f.poll(&mut Context::from_waker(waker))))
+                })
+            });
+            match r {
+                Ok(Poll::Ready(value)) => {
+                    this.task.complete(Ok(value));
+                    false
+                }
+                Ok(Poll::Pending) => true,
+                Err(payload) => {
+                    if Runtime::with(|rt| rt.panic_policy) == PanicPolicy::Propagate {
+                        resume_unwind(payload);
+                    }
+                    this.task
+                        .complete(Err(JoinError::panic(payload, this.task.info.clone())));
                     false
-                } else {
-                    true
                 }
             }
         }
     }
 }
+impl<F: Future> Drop for RawRunnable<F> {
+    fn drop(&mut self) {
+        self.future = None;
+        self.task.on_drop_future();
+    }
+}
 
 struct Runner {
     rc: RequestChannel,
     wakes: Vec<usize>,
     drops: Vec<usize>,
-    rs: SlabMap<Option<Runnable>>,
+    timer_wakers: Vec<Waker>,
+    dispatches: Vec<Dispatch>,
+    queues: [VecDeque<usize>; Priority::COUNT],
+    main_priority: Priority,
+    rs: SlabMap<Option<Runnable<'static>>>,
+    #[cfg(feature = "tokio")]
+    tokio: crate::tokio_support::TokioContext,
 }
 
 impl Runner {
@@ -400,10 +843,25 @@ impl Runner {
             rc: RequestChannel::new(waker),
             wakes: Vec::new(),
             drops: Vec::new(),
+            timer_wakers: Vec::new(),
+            dispatches: Vec::new(),
+            queues: Default::default(),
+            main_priority: Priority::Normal,
             rs: SlabMap::new(),
+            #[cfg(feature = "tokio")]
+            tokio: crate::tokio_support::TokioContext::new(),
         }
     }
     fn ready_requests(&mut self) {
+        let now = Instant::now();
+        Runtime::with(|rt| rt.timers.pop_expired(now, &mut self.timer_wakers));
+        for waker in self.timer_wakers.drain(..) {
+            waker.wake();
+        }
+        self.rc.get_dispatches(&mut self.dispatches);
+        for f in self.dispatches.drain(..) {
+            f();
+        }
         self.rc.get_wakes_drops(&mut self.wakes, &mut self.drops);
         Runtime::with(|rt| {
             for r in rt.rs.drain(..) {
@@ -413,6 +871,10 @@ impl Runner {
                 );
             }
         });
+        for id in self.wakes.drain(..) {
+            let priority = priority_of(id, self.main_priority, &self.rs);
+            self.queues[priority as usize].push_back(id);
+        }
     }
     fn apply_drops(&mut self) {
         for id in self.drops.drain(..) {
@@ -420,22 +882,100 @@ impl Runner {
         }
     }
 
-    fn poll(&mut self) {
-        self.ready_requests();
-        for id in self.wakes.drain(..) {
-            run_item(&mut self.rs[id]);
+    fn poll_with<T>(
+        &mut self,
+        options: &PollOptions,
+        mut run_main: impl FnMut() -> ControlFlow<T>,
+    ) -> ControlFlow<T, PollResult> {
+        #[cfg(feature = "tokio")]
+        let _tokio = self.tokio.enter();
+        let mut polls = 0;
+        loop {
+            self.ready_requests();
+            self.run_queues(options, &mut polls, &mut run_main)?;
+            self.apply_drops();
+            let remaining = self.has_remaining();
+            if !remaining || !options.drain_until_stable || is_limit_reached(options, polls) {
+                if self.queues.iter().any(|q| !q.is_empty()) {
+                    self.rc.0.waker.wake_by_ref();
+                }
+                return ControlFlow::Continue(PollResult { polls, remaining });
+            }
+        }
+    }
+    fn run_queues<T>(
+        &mut self,
+        options: &PollOptions,
+        polls: &mut usize,
+        run_main: &mut impl FnMut() -> ControlFlow<T>,
+    ) -> ControlFlow<T> {
+        let budget = Runtime::with(|rt| rt.coop_budget);
+        for priority in [Priority::High, Priority::Normal, Priority::Background] {
+            let queue = priority as usize;
+            while !self.queues[queue].is_empty() {
+                if is_limit_reached(options, *polls)
+                    || (priority == Priority::Background && self.has_foreground())
+                {
+                    return ControlFlow::Continue(());
+                }
+                let id = self.queues[queue].pop_front().unwrap();
+                *polls += 1;
+                self.run_id(id, budget, run_main)?;
+            }
         }
-        self.apply_drops();
+        ControlFlow::Continue(())
+    }
+    fn run_id<T>(
+        &mut self,
+        id: usize,
+        budget: Option<u32>,
+        run_main: &mut impl FnMut() -> ControlFlow<T>,
+    ) -> ControlFlow<T> {
+        with_budget(budget, || {
+            if id == ID_MAIN {
+                run_main()
+            } else {
+                // A queued id may outlive its task when polling stopped before reaching it.
+                if let Some(r) = self.rs.get_mut(id) {
+                    run_item(r);
+                }
+                ControlFlow::Continue(())
+            }
+        })
+    }
+    fn has_foreground(&self) -> bool {
+        self.rc
+            .has_wake(|id| priority_of(id, self.main_priority, &self.rs) != Priority::Background)
+            || Runtime::with(|rt| rt.rs.iter().any(|r| r.priority() != Priority::Background))
+    }
+
+    fn has_remaining(&self) -> bool {
+        self.queues.iter().any(|q| !q.is_empty())
+            || self.rc.has_wake(|_| true)
+            || Runtime::with(|rt| !rt.rs.is_empty())
+    }
+}
+fn is_limit_reached(options: &PollOptions, polls: usize) -> bool {
+    options.max_polls.is_some_and(|max| polls >= max)
+        || options.deadline.is_some_and(|d| Instant::now() >= d)
+}
+fn priority_of(id: usize, main_priority: Priority, rs: &SlabMap<Option<Runnable>>) -> Priority {
+    if id == ID_MAIN {
+        main_priority
+    } else if let Some(Some(r)) = rs.get(id) {
+        r.r.priority()
+    } else {
+        Priority::Normal
     }
 }
 
-struct Runnable {
+pub(crate) struct Runnable<'a> {
     wake: Arc<TaskWake>,
-    r: Pin<Box<dyn DynRunnable>>,
+    r: Pin<Box<dyn DynRunnable + 'a>>,
 }
 
-impl Runnable {
-    fn new(r: Pin<Box<dyn DynRunnable>>, id: usize, rc: &RequestChannel) -> Self {
+impl<'a> Runnable<'a> {
+    pub(crate) fn new(r: Pin<Box<dyn DynRunnable + 'a>>, id: usize, rc: &RequestChannel) -> Self {
         r.as_ref().set_id(id);
         Self {
             wake: TaskWake::new(id, rc),
@@ -446,7 +986,7 @@ impl Runnable {
         self.r.as_mut().run(&self.wake.waker())
     }
 }
-fn run_item(r: &mut Option<Runnable>) {
+pub(crate) fn run_item(r: &mut Option<Runnable>) {
     if let Some(runnable) = r {
         if !runnable.run() {
             r.take();
@@ -0,0 +1,61 @@
+use crate::base_impl::Runtime;
+use std::{
+    cell::Cell,
+    future::poll_fn,
+    task::{Context, Poll},
+};
+
+thread_local! {
+    static BUDGET: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Set the number of operations that a task can perform before being forced to yield back to the runtime.
+///
+/// When the budget of a task is exhausted, futures provided by rt-local such as [`Task`](crate::Task), [`Sleep`](crate::time::Sleep) and channel receivers in [`sync`](crate::sync)
+/// return [`Poll::Pending`] even if they are ready, and the task is polled again after the other woken tasks.
+///
+/// The budget is reset each time the task is polled. `None` (the default) disables budgeting.
+///
+/// # Panics
+///
+/// Panics if the runtime is not running.
+pub fn set_coop_budget(budget: Option<u32>) {
+    Runtime::with(|rt| rt.coop_budget = budget)
+}
+
+/// Consume one unit of the budget of the current task, yielding if the budget is exhausted.
+///
+/// Use this in loops that do not use futures provided by rt-local.
+/// See [`set_coop_budget`] for details.
+pub async fn consume_budget() {
+    poll_fn(|cx| poll_budget(cx, |_| Poll::Ready(()))).await
+}
+
+pub(crate) fn with_budget<T>(budget: Option<u32>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<u32>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            BUDGET.with(|b| b.set(self.0));
+        }
+    }
+    let _restore = Restore(BUDGET.with(|b| b.replace(budget)));
+    f()
+}
+
+pub(crate) fn poll_budget<T>(
+    cx: &mut Context<'_>,
+    f: impl FnOnce(&mut Context<'_>) -> Poll<T>,
+) -> Poll<T> {
+    let budget = BUDGET.with(|b| b.get());
+    if budget == Some(0) {
+        cx.waker().wake_by_ref();
+        return Poll::Pending;
+    }
+    let p = f(cx);
+    if p.is_ready() {
+        if let Some(budget) = budget {
+            BUDGET.with(|b| b.set(Some(budget - 1)));
+        }
+    }
+    p
+}
@@ -0,0 +1,288 @@
+use crate::{
+    base_impl::{spawn_local_with, Dispatch, RequestChannel, Runtime},
+    spawn_local,
+    task::{AbortHandle, Join, JoinError, Priority, TaskInfo},
+};
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe, Location},
+    pin::Pin,
+    sync::{mpsc, Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A handle to run functions and futures on the thread of a running runtime from other threads.
+///
+/// Functions are delivered through the same channel that wakes the runtime backend,
+/// so they are run promptly regardless of the backend.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::Dispatcher;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let dispatcher = Dispatcher::current();
+/// let value = std::thread::spawn(move || {
+///     let data = 10; // computed on a worker thread
+///     dispatcher.spawn(move || async move { data * 2 })
+/// })
+/// .join()
+/// .unwrap()
+/// .await
+/// .unwrap();
+/// assert_eq!(value, 20);
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct Dispatcher {
+    rc: RequestChannel,
+}
+
+impl Dispatcher {
+    /// Returns a dispatcher for the runtime running on the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime is not running.
+    #[track_caller]
+    pub fn current() -> Self {
+        Runtime::with(|rt| Self { rc: rt.rc.clone() })
+    }
+
+    /// Returns a dispatcher for the runtime running on the current thread, or `None` if the runtime is not running.
+    pub fn try_current() -> Option<Self> {
+        Runtime::try_with(|rt| Self { rc: rt.rc.clone() })
+    }
+
+    /// Call `f` on the runtime thread.
+    ///
+    /// `f` is called in a task spawned by [`spawn_local`], so it can spawn other tasks,
+    /// and a panic in `f` is handled according to [`PanicPolicy`](crate::task::PanicPolicy).
+    ///
+    /// Returns [`DispatchError`] if the runtime has stopped.
+    /// If the runtime stops before `f` is called, `f` is dropped without being called.
+    pub fn dispatch(&self, f: impl FnOnce() + Send + 'static) -> Result<(), DispatchError> {
+        self.push(Box::new(move || spawn_local(async move { f() }).detach()))
+    }
+
+    /// Create a future by calling `f` on the runtime thread, and spawn it there.
+    ///
+    /// Only `f` and the output need to be `Send`; the future itself runs on the runtime thread.
+    /// The returned [`DispatchTask`] can be awaited from any thread.
+    /// When it is dropped, the task is canceled.
+    #[track_caller]
+    pub fn spawn<F, Fut>(&self, f: F) -> DispatchTask<Fut::Output>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let info = TaskInfo::new(None, Location::caller(), Priority::Normal);
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            is_completed: false,
+            waker: None,
+            local_waker: None,
+            is_cancelled: false,
+        }));
+        let completer = Completer {
+            shared: shared.clone(),
+            info: info.clone(),
+        };
+        let _ = self.push(Box::new(move || {
+            let task = spawn_local_with(async move { f().await }, info);
+            spawn_local(Forward {
+                abort: task.abort_handle(),
+                join: task.join(),
+                completer,
+            })
+            .detach();
+        }));
+        DispatchTask {
+            shared,
+            is_detach: false,
+        }
+    }
+
+    /// Call `f` on the runtime thread and block the current thread until it returns.
+    ///
+    /// Unlike [`Dispatcher::dispatch`], `f` is called directly by the runtime rather than in a spawned task.
+    /// If `f` panics, the panic is resumed on the current thread.
+    ///
+    /// Returns [`DispatchError`] if the runtime has stopped before `f` is called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called on the runtime thread, since that would deadlock.
+    #[track_caller]
+    pub fn invoke<R: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> R + Send + 'static,
+    ) -> Result<R, DispatchError> {
+        if self.rc.is_running_on_current_thread() {
+            panic!("`Dispatcher::invoke` called on the runtime thread");
+        }
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.push(Box::new(move || {
+            let _ = tx.send(catch_unwind(AssertUnwindSafe(f)));
+        }))?;
+        match rx.recv() {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(payload)) => resume_unwind(payload),
+            Err(_) => Err(DispatchError(())),
+        }
+    }
+
+    fn push(&self, f: Dispatch) -> Result<(), DispatchError> {
+        self.rc.push_dispatch(f).map_err(|_| DispatchError(()))
+    }
+}
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher").finish_non_exhaustive()
+    }
+}
+
+struct Shared<T> {
+    result: Option<Result<T, JoinError>>,
+    is_completed: bool,
+    waker: Option<Waker>,
+    local_waker: Option<Waker>,
+    is_cancelled: bool,
+}
+
+/// Completes [`DispatchTask`] with an error if dropped before the task completes.
+struct Completer<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    info: TaskInfo,
+}
+impl<T> Completer<T> {
+    fn complete(&self, result: Result<T, JoinError>) {
+        let waker = {
+            let mut s = self.shared.lock().unwrap();
+            s.result = Some(result);
+            s.is_completed = true;
+            s.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        let is_completed = self.shared.lock().unwrap().is_completed;
+        if !is_completed {
+            self.complete(Err(JoinError::cancelled(self.info.clone())));
+        }
+    }
+}
+
+/// Forwards the result of the spawned task to [`DispatchTask`], and cancels it when requested.
+struct Forward<T> {
+    join: Join<T>,
+    abort: AbortHandle,
+    completer: Completer<T>,
+}
+impl<T: 'static> Future for Forward<T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let is_cancelled = {
+            let mut s = this.completer.shared.lock().unwrap();
+            s.local_waker = Some(cx.waker().clone());
+            s.is_cancelled
+        };
+        if is_cancelled {
+            this.abort.abort();
+        }
+        match Pin::new(&mut this.join).poll(cx) {
+            Poll::Ready(result) => {
+                this.completer.complete(result);
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A task spawned by [`Dispatcher::spawn`], which can be awaited from any thread.
+///
+/// Awaiting it returns the output of the task, or [`JoinError`] if the task panicked, was canceled, or the runtime stopped.
+///
+/// When a [`DispatchTask`] is dropped, the task is canceled.
+/// To drop it without canceling, call [`DispatchTask::detach`].
+#[must_use = "dropping a DispatchTask cancels the task"]
+pub struct DispatchTask<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    is_detach: bool,
+}
+
+impl<T> DispatchTask<T> {
+    /// Drop the handle without canceling the task.
+    pub fn detach(mut self) {
+        self.is_detach = true;
+    }
+
+    /// Request cancellation of the task.
+    pub fn abort(&self) {
+        let waker = {
+            let mut s = self.shared.lock().unwrap();
+            if s.is_completed {
+                return;
+            }
+            s.is_cancelled = true;
+            s.local_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.shared.lock().unwrap().is_completed
+    }
+}
+impl<T> Future for DispatchTask<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut s = self.shared.lock().unwrap();
+        if let Some(result) = s.result.take() {
+            Poll::Ready(result)
+        } else {
+            s.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+impl<T> Drop for DispatchTask<T> {
+    fn drop(&mut self) {
+        if !self.is_detach {
+            self.abort();
+        }
+    }
+}
+impl<T> fmt::Debug for DispatchTask<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DispatchTask")
+            .field("is_finished", &self.is_finished())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Dispatcher`] when the runtime has stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchError(());
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "runtime has stopped")
+    }
+}
+impl Error for DispatchError {}
@@ -0,0 +1,196 @@
+use crate::{
+    spawn_local,
+    task::{AbortHandle, Join, JoinError},
+};
+use futures_core::Stream;
+use slabmap::SlabMap;
+use std::{
+    fmt,
+    future::{poll_fn, Future},
+    mem::take,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+};
+
+/// A collection of tasks spawned on the current thread.
+///
+/// Tasks can be awaited in the order they complete by [`LocalJoinSet::join_next`].
+/// When a [`LocalJoinSet`] is dropped, all tasks in it are canceled.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::task::LocalJoinSet;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let mut set = LocalJoinSet::new();
+/// for i in 0..3 {
+///     set.spawn(async move { i });
+/// }
+/// let mut sum = 0;
+/// while let Some(value) = set.join_next().await {
+///     sum += value.unwrap();
+/// }
+/// assert_eq!(sum, 3);
+/// # });
+/// ```
+pub struct LocalJoinSet<T> {
+    tasks: SlabMap<Entry<T>>,
+    wake: Arc<JoinSetWake>,
+}
+
+struct Entry<T> {
+    join: Join<T>,
+    abort: AbortHandle,
+    waker: Waker,
+}
+
+impl<T: 'static> LocalJoinSet<T> {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self {
+            tasks: SlabMap::new(),
+            wake: Arc::new(JoinSetWake {
+                ready: Mutex::new(Vec::new()),
+                waker: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Spawn a future on the current thread and add it to the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime is not running.
+    #[track_caller]
+    pub fn spawn(&mut self, future: impl Future<Output = T> + 'static) -> AbortHandle {
+        let task = spawn_local(future);
+        let abort = task.abort_handle();
+        let wake = &self.wake;
+        let id = self.tasks.insert_with_key(|id| Entry {
+            join: task.join(),
+            abort: abort.clone(),
+            waker: Arc::new(EntryWake {
+                id,
+                set: wake.clone(),
+            })
+            .into(),
+        });
+        self.wake.ready.lock().unwrap().push(id);
+        abort
+    }
+
+    /// Returns the number of tasks in the set.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns `true` if the set contains no tasks.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Wait until one of the tasks in the set completes and returns its output.
+    ///
+    /// Returns `None` if the set is empty.
+    pub async fn join_next(&mut self) -> Option<Result<T, JoinError>> {
+        poll_fn(|cx| self.poll_join_next(cx)).await
+    }
+
+    /// Polls for one of the tasks in the set to complete.
+    ///
+    /// Returns `Poll::Ready(None)` if the set is empty.
+    pub fn poll_join_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, JoinError>>> {
+        if self.tasks.is_empty() {
+            return Poll::Ready(None);
+        }
+        *self.wake.waker.lock().unwrap() = Some(cx.waker().clone());
+        let mut ready = take(&mut *self.wake.ready.lock().unwrap());
+        let mut result = Poll::Pending;
+        let mut ids = ready.drain(..);
+        for id in ids.by_ref() {
+            let Some(e) = self.tasks.get_mut(id) else {
+                continue;
+            };
+            if let Poll::Ready(value) =
+                Pin::new(&mut e.join).poll(&mut Context::from_waker(&e.waker))
+            {
+                self.tasks.remove(id);
+                result = Poll::Ready(Some(value));
+                break;
+            }
+        }
+        let rest: Vec<_> = ids.collect();
+        if !rest.is_empty() {
+            self.wake.ready.lock().unwrap().extend(rest);
+            cx.waker().wake_by_ref();
+        }
+        result
+    }
+
+    /// Cancel all tasks in the set.
+    ///
+    /// The tasks remain in the set, and [`LocalJoinSet::join_next`] returns [`JoinError`] for them.
+    pub fn abort_all(&mut self) {
+        for e in self.tasks.values() {
+            e.abort.abort();
+        }
+    }
+
+    /// Remove all tasks from the set without canceling them.
+    pub fn detach_all(&mut self) {
+        for (_, e) in self.tasks.drain() {
+            e.join.detach();
+        }
+        self.wake.ready.lock().unwrap().clear();
+    }
+}
+
+impl<T: 'static> Default for LocalJoinSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for LocalJoinSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalJoinSet")
+            .field("len", &self.tasks.len())
+            .finish()
+    }
+}
+
+impl<T: 'static> Stream for LocalJoinSet<T> {
+    type Item = Result<T, JoinError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_join_next(cx)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+struct JoinSetWake {
+    ready: Mutex<Vec<usize>>,
+    waker: Mutex<Option<Waker>>,
+}
+
+struct EntryWake {
+    id: usize,
+    set: Arc<JoinSetWake>,
+}
+
+impl Wake for EntryWake {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.set.ready.lock().unwrap().push(self.id);
+        let waker = self.set.waker.lock().unwrap().clone();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
@@ -1,9 +1,31 @@
 mod base_impl;
-pub use crate::base_impl::{spawn_local, wait_for_idle, Task};
+mod coop;
+mod dispatcher;
+mod join_set;
+mod pool;
+mod scope;
+pub mod sync;
+pub mod task;
+mod task_local;
+mod thread_pool;
+pub mod time;
+#[cfg(feature = "tokio")]
+mod tokio_support;
+
+pub use crate::base_impl::{
+    spawn_local, spawn_local_with_priority, wait_for_idle, yield_now, Task,
+};
+pub use crate::dispatcher::{DispatchError, DispatchTask, Dispatcher};
+pub use crate::pool::LocalPool;
+pub use crate::scope::{scope, Scope};
+pub use crate::thread_pool::{spawn_blocking, spawn_thread, BlockingTask};
 
 /// Components to implement runtime.
 pub mod base {
-    pub use crate::base_impl::{enter, idle, leave, poll, run, EventLoop};
+    pub use crate::base_impl::{
+        enter, idle, leave, next_deadline, poll, poll_with, run, run_with, run_with_priority,
+        EventLoop, PollOptions, PollResult, RunOptions,
+    };
 }
 /// Runtime implementations.
 pub mod runtime {
@@ -0,0 +1,239 @@
+use crate::{runtime::blocking, DispatchTask, Dispatcher};
+use std::{
+    fmt,
+    future::{poll_fn, Future},
+    sync::{mpsc, Arc, Mutex},
+    task::{Poll, Waker},
+    thread,
+};
+
+/// A pool of threads, each running its own runtime.
+///
+/// Futures spawned on the pool do not need to be `Send`; only the closure creating them and their output do.
+/// Each future stays on the thread it was spawned on.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::LocalPool;
+/// use std::rc::Rc;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let pool = LocalPool::new(2);
+/// let value = pool
+///     .spawn_pinned(|| async {
+///         let value = Rc::new(10); // `!Send` values can be held across `.await`
+///         rt_local_core::yield_now().await;
+///         *value
+///     })
+///     .await
+///     .unwrap();
+/// assert_eq!(value, 10);
+/// pool.shutdown().await;
+/// # });
+/// ```
+pub struct LocalPool {
+    workers: Vec<Worker>,
+}
+
+struct Worker {
+    dispatcher: Dispatcher,
+    state: Arc<Mutex<WorkerState>>,
+}
+
+struct WorkerState {
+    load: usize,
+    is_shutdown: bool,
+    is_stopped: bool,
+    main_waker: Option<Waker>,
+    shutdown_waker: Option<Waker>,
+}
+
+impl LocalPool {
+    /// Start `n` threads, each running [`runtime::blocking::run`](crate::runtime::blocking::run).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero, or if a thread cannot be created.
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0, "`n` must be non-zero");
+        let workers = (0..n)
+            .map(|index| {
+                let state = Arc::new(Mutex::new(WorkerState {
+                    load: 0,
+                    is_shutdown: false,
+                    is_stopped: false,
+                    main_waker: None,
+                    shutdown_waker: None,
+                }));
+                let (tx, rx) = mpsc::sync_channel(1);
+                thread::Builder::new()
+                    .name(format!("rt-local-pool-{index}"))
+                    .spawn({
+                        let state = state.clone();
+                        move || worker_main(state, tx)
+                    })
+                    .expect("failed to spawn thread");
+                Worker {
+                    dispatcher: rx.recv().unwrap(),
+                    state,
+                }
+            })
+            .collect();
+        Self { workers }
+    }
+
+    /// Returns the number of threads in the pool.
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Returns `true` if the pool has no threads.
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    /// Spawn a future created by `f` on the thread with the fewest running futures.
+    ///
+    /// See [`Dispatcher::spawn`] for details.
+    #[track_caller]
+    pub fn spawn_pinned<F, Fut>(&self, f: F) -> DispatchTask<Fut::Output>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let index = (0..self.workers.len())
+            .min_by_key(|&i| self.workers[i].state.lock().unwrap().load)
+            .unwrap();
+        self.spawn_on(index, f)
+    }
+
+    /// Spawn a future created by `f` on the thread at `index`.
+    ///
+    /// See [`Dispatcher::spawn`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than or equal to [`LocalPool::len`].
+    #[track_caller]
+    pub fn spawn_on<F, Fut>(&self, index: usize, f: F) -> DispatchTask<Fut::Output>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let worker = &self.workers[index];
+        worker.state.lock().unwrap().load += 1;
+        let load = LoadGuard(worker.state.clone());
+        worker.dispatcher.spawn(move || {
+            let future = f();
+            async move {
+                let _load = load;
+                future.await
+            }
+        })
+    }
+
+    /// Returns the [`Dispatcher`] of the thread at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than or equal to [`LocalPool::len`].
+    pub fn dispatcher(&self, index: usize) -> &Dispatcher {
+        &self.workers[index].dispatcher
+    }
+
+    /// Wait for the futures spawned by [`LocalPool::spawn_pinned`] and [`LocalPool::spawn_on`] to complete, then stop all threads.
+    ///
+    /// Other tasks running on the threads are canceled when the threads stop.
+    pub async fn shutdown(mut self) {
+        let workers = std::mem::take(&mut self.workers);
+        for w in &workers {
+            w.request_shutdown();
+        }
+        for w in &workers {
+            poll_fn(|cx| {
+                let mut s = w.state.lock().unwrap();
+                if s.is_stopped {
+                    Poll::Ready(())
+                } else {
+                    s.shutdown_waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            })
+            .await;
+        }
+    }
+}
+impl Drop for LocalPool {
+    fn drop(&mut self) {
+        for w in &self.workers {
+            w.request_shutdown();
+        }
+    }
+}
+impl fmt::Debug for LocalPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalPool")
+            .field("len", &self.workers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Worker {
+    fn request_shutdown(&self) {
+        let waker = {
+            let mut s = self.state.lock().unwrap();
+            s.is_shutdown = true;
+            s.main_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+fn worker_main(state: Arc<Mutex<WorkerState>>, tx: mpsc::SyncSender<Dispatcher>) {
+    blocking::run(async {
+        tx.send(Dispatcher::current()).unwrap();
+        poll_fn(|cx| {
+            let mut s = state.lock().unwrap();
+            if s.is_shutdown && s.load == 0 {
+                Poll::Ready(())
+            } else {
+                s.main_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    });
+    let waker = {
+        let mut s = state.lock().unwrap();
+        s.is_stopped = true;
+        s.shutdown_waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// Counts a future spawned on a worker while it exists.
+struct LoadGuard(Arc<Mutex<WorkerState>>);
+
+impl Drop for LoadGuard {
+    fn drop(&mut self) {
+        let waker = {
+            let mut s = self.0.lock().unwrap();
+            s.load -= 1;
+            if s.load == 0 && s.is_shutdown {
+                s.main_waker.take()
+            } else {
+                None
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
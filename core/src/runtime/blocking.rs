@@ -1,9 +1,11 @@
 use crate::base::{idle, EventLoop};
 use std::{
+    cell::Cell,
     future::Future,
     ops::ControlFlow,
     sync::{Arc, Condvar, Mutex},
     task::Wake,
+    time::Instant,
 };
 
 /// Executes the specified future and blocks until it completes.
@@ -11,7 +13,10 @@ pub fn run<T>(future: impl Future<Output = T>) -> T {
     crate::base::run(&BlockingEventLoop::new(), future)
 }
 
-struct BlockingEventLoop(Arc<Waker>);
+struct BlockingEventLoop {
+    waker: Arc<Waker>,
+    deadline: Cell<Option<Instant>>,
+}
 
 struct Waker {
     is_wake: Mutex<bool>,
@@ -20,10 +25,13 @@ struct Waker {
 
 impl BlockingEventLoop {
     pub fn new() -> Self {
-        Self(Arc::new(Waker {
-            is_wake: Mutex::new(true),
-            cv: Condvar::new(),
-        }))
+        Self {
+            waker: Arc::new(Waker {
+                is_wake: Mutex::new(true),
+                cv: Condvar::new(),
+            }),
+            deadline: Cell::new(None),
+        }
     }
 }
 
@@ -35,10 +43,10 @@ impl Default for BlockingEventLoop {
 
 impl EventLoop for BlockingEventLoop {
     fn waker(&self) -> std::task::Waker {
-        self.0.clone().into()
+        self.waker.clone().into()
     }
     fn run<T>(&self, mut poll: impl FnMut() -> ControlFlow<T>) -> T {
-        let mut is_wake = self.0.is_wake.lock().unwrap();
+        let mut is_wake = self.waker.is_wake.lock().unwrap();
         loop {
             is_wake = if *is_wake {
                 *is_wake = false;
@@ -49,12 +57,20 @@ impl EventLoop for BlockingEventLoop {
                     }
                     idle()
                 } {}
-                self.0.is_wake.lock().unwrap()
+                self.waker.is_wake.lock().unwrap()
+            } else if let Some(deadline) = self.deadline.get() {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                let (mut is_wake, _) = self.waker.cv.wait_timeout(is_wake, timeout).unwrap();
+                *is_wake = true;
+                is_wake
             } else {
-                self.0.cv.wait(is_wake).unwrap()
+                self.waker.cv.wait(is_wake).unwrap()
             }
         }
     }
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+    }
 }
 
 impl Wake for Waker {
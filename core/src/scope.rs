@@ -0,0 +1,172 @@
+use crate::{
+    base_impl::{new_task, run_item, DynRunnable, RequestChannel, Runnable},
+    task::{Priority, TaskInfo},
+    Task,
+};
+use slabmap::SlabMap;
+use std::{
+    cell::{Cell, RefCell},
+    future::{poll_fn, Future},
+    mem::take,
+    panic::Location,
+    pin::{pin, Pin},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    task::{Poll, Wake, Waker},
+};
+
+/// Run a future that can spawn tasks borrowing non-`'static` data.
+///
+/// Tasks spawned by [`Scope::spawn`] are polled by the future returned by this function.
+/// The future completes after `f`'s future and all spawned tasks have completed.
+/// When the future is dropped, all spawned tasks are canceled and their futures are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::scope;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let data = vec![1, 2, 3];
+/// let data = &data;
+/// let sum = scope(|s| async move {
+///     let a = s.spawn(async move { data[0] + data[1] });
+///     let b = s.spawn(async move { data[2] });
+///     a.await + b.await
+/// })
+/// .await;
+/// assert_eq!(sum, 6);
+/// # });
+/// ```
+pub async fn scope<'env, F, Fut>(f: F) -> Fut::Output
+where
+    F: FnOnce(Scope<'env>) -> Fut,
+    Fut: Future,
+{
+    let mut state = ScopeState::new();
+    let mut body = pin!(f(state.scope.clone()));
+    let mut output = None;
+    poll_fn(|cx| {
+        state.waker.set(cx.waker());
+        if output.is_none() {
+            if let Poll::Ready(value) = body.as_mut().poll(cx) {
+                output = Some(value);
+            }
+        }
+        state.poll_tasks();
+        if output.is_some() && state.is_empty() {
+            Poll::Ready(output.take().unwrap())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// A handle to spawn tasks in a [`scope`].
+#[derive(Clone)]
+pub struct Scope<'env>(Rc<ScopeShared<'env>>);
+
+struct ScopeShared<'env> {
+    rc: RequestChannel,
+    waker: Arc<ScopeWaker>,
+    rs: RefCell<Vec<Pin<Box<dyn DynRunnable + 'env>>>>,
+    is_closed: Cell<bool>,
+}
+
+impl<'env> Scope<'env> {
+    /// Spawn a future in the scope.
+    ///
+    /// The returned [`Task`] behaves like one returned by [`spawn_local`](crate::spawn_local).
+    /// Tasks detached by [`Task::detach`] are still awaited before the scope completes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scope has already completed or has been dropped.
+    #[track_caller]
+    pub fn spawn<F: Future + 'env>(&self, future: F) -> Task<F::Output> {
+        if self.0.is_closed.get() {
+            panic!("scope has already finished");
+        }
+        let info = TaskInfo::new(None, Location::caller(), Priority::Normal);
+        let (r, task) = new_task(future, info, &self.0.rc);
+        self.0.rs.borrow_mut().push(r);
+        self.0.waker.wake_by_ref();
+        task
+    }
+}
+
+struct ScopeState<'env> {
+    scope: Scope<'env>,
+    waker: Arc<ScopeWaker>,
+    wakes: Vec<usize>,
+    drops: Vec<usize>,
+    rs: SlabMap<Option<Runnable<'env>>>,
+}
+
+impl<'env> ScopeState<'env> {
+    fn new() -> Self {
+        let waker = Arc::new(ScopeWaker(Mutex::new(None)));
+        let scope = Scope(Rc::new(ScopeShared {
+            rc: RequestChannel::new(waker.clone().into()),
+            waker: waker.clone(),
+            rs: RefCell::new(Vec::new()),
+            is_closed: Cell::new(false),
+        }));
+        Self {
+            scope,
+            waker,
+            wakes: Vec::new(),
+            drops: Vec::new(),
+            rs: SlabMap::new(),
+        }
+    }
+    fn poll_tasks(&mut self) {
+        let shared = &self.scope.0;
+        shared.rc.get_wakes_drops(&mut self.wakes, &mut self.drops);
+        for r in take(&mut *shared.rs.borrow_mut()) {
+            self.wakes.push(
+                self.rs
+                    .insert_with_key(|id| Some(Runnable::new(r, id, &shared.rc))),
+            );
+        }
+        for id in self.wakes.drain(..) {
+            run_item(&mut self.rs[id]);
+        }
+        for id in self.drops.drain(..) {
+            self.rs.remove(id);
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.rs.values().all(|r| r.is_none()) && self.scope.0.rs.borrow().is_empty()
+    }
+}
+impl Drop for ScopeState<'_> {
+    fn drop(&mut self) {
+        self.scope.0.is_closed.set(true);
+        self.rs.clear();
+        take(&mut *self.scope.0.rs.borrow_mut());
+    }
+}
+
+struct ScopeWaker(Mutex<Option<Waker>>);
+
+impl ScopeWaker {
+    fn set(&self, waker: &Waker) {
+        let mut w = self.0.lock().unwrap();
+        if !w.as_ref().is_some_and(|w| w.will_wake(waker)) {
+            *w = Some(waker.clone());
+        }
+    }
+}
+impl Wake for ScopeWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        let waker = self.0.lock().unwrap().clone();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! Synchronization primitives for tasks running on the same thread.
+//!
+//! These types are not `Send`, and do not use atomic operations or locks.
+//! Tasks waiting on them are served in the order they started waiting,
+//! and dropping a waiting future removes it from the queue.
+pub mod broadcast;
+pub mod mpsc;
+pub mod oneshot;
+pub mod watch;
+
+mod barrier;
+mod mutex;
+mod notify;
+mod rwlock;
+mod semaphore;
+mod wait_queue;
+
+pub use self::barrier::{LocalBarrier, LocalBarrierWaitResult};
+pub use self::mutex::{LocalMutex, LocalMutexGuard};
+pub use self::notify::{LocalNotify, Notified};
+pub use self::rwlock::{LocalRwLock, LocalRwLockReadGuard, LocalRwLockWriteGuard};
+pub use self::semaphore::{
+    LocalSemaphore, LocalSemaphorePermit, OwnedLocalSemaphorePermit, TryAcquireError,
+};
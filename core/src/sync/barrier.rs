@@ -0,0 +1,111 @@
+use super::wait_queue::WaitQueue;
+use std::{cell::Cell, fmt, future::poll_fn, task::Poll};
+
+/// A barrier that lets a fixed number of tasks running on the same thread wait for each other.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::{spawn_local, sync::LocalBarrier};
+/// use std::rc::Rc;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let barrier = Rc::new(LocalBarrier::new(3));
+/// let tasks: Vec<_> = (0..3)
+///     .map(|_| {
+///         let barrier = barrier.clone();
+///         spawn_local(async move { barrier.wait().await.is_leader() })
+///     })
+///     .collect();
+/// let mut leaders = 0;
+/// for task in tasks {
+///     leaders += task.await as usize;
+/// }
+/// assert_eq!(leaders, 1);
+/// # });
+/// ```
+pub struct LocalBarrier {
+    n: usize,
+    arrived: Cell<usize>,
+    generation: Cell<u64>,
+    waiters: WaitQueue,
+}
+
+impl LocalBarrier {
+    /// Create a barrier that releases tasks when `n` tasks are waiting.
+    ///
+    /// A barrier with `n` of zero behaves the same as one with `n` of one.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n: n.max(1),
+            arrived: Cell::new(0),
+            generation: Cell::new(0),
+            waiters: WaitQueue::new(),
+        }
+    }
+
+    /// Wait until `n` tasks are waiting on this barrier.
+    ///
+    /// The barrier can be reused after all tasks are released.
+    /// If the future is dropped before all tasks arrive, it is no longer counted as waiting.
+    pub async fn wait(&self) -> LocalBarrierWaitResult {
+        let generation = self.generation.get();
+        let arrived = self.arrived.get() + 1;
+        if arrived == self.n {
+            self.arrived.set(0);
+            self.generation.set(generation + 1);
+            self.waiters.wake_all();
+            return LocalBarrierWaitResult(true);
+        }
+        self.arrived.set(arrived);
+        let mut waiter = Waiter {
+            barrier: self,
+            generation,
+            key: None,
+        };
+        poll_fn(|cx| {
+            if self.generation.get() != generation {
+                self.waiters.remove(&mut waiter.key);
+                Poll::Ready(LocalBarrierWaitResult(false))
+            } else {
+                self.waiters.register(&mut waiter.key, cx);
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+impl fmt::Debug for LocalBarrier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalBarrier")
+            .field("n", &self.n)
+            .field("arrived", &self.arrived.get())
+            .finish_non_exhaustive()
+    }
+}
+
+struct Waiter<'a> {
+    barrier: &'a LocalBarrier,
+    generation: u64,
+    key: Option<u64>,
+}
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        let b = self.barrier;
+        b.waiters.remove(&mut self.key);
+        if b.generation.get() == self.generation {
+            b.arrived.set(b.arrived.get() - 1);
+        }
+    }
+}
+
+/// Result returned by [`LocalBarrier::wait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalBarrierWaitResult(bool);
+
+impl LocalBarrierWaitResult {
+    /// Returns `true` for exactly one task of each release: the task that arrived last.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
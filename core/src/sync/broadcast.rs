@@ -0,0 +1,294 @@
+//! A multi-producer, multi-consumer channel where each receiver sees every value.
+//!
+//! The channel holds at most `capacity` values.
+//! When a receiver falls behind, the oldest values are dropped and the receiver gets [`RecvError::Lagged`].
+//!
+//! # Examples
+//!
+//! ```
+//! use rt_local_core::sync::broadcast;
+//!
+//! # rt_local_core::runtime::blocking::run(async {
+//! let (tx, mut rx1) = broadcast::channel(16);
+//! let mut rx2 = tx.subscribe();
+//! tx.send(10).unwrap();
+//! assert_eq!(rx1.recv().await, Ok(10));
+//! assert_eq!(rx2.recv().await, Ok(10));
+//! # });
+//! ```
+use super::wait_queue::WaitQueue;
+use crate::coop::poll_budget;
+use futures_core::Stream;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::poll_fn,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+/// Create a broadcast channel that holds at most `capacity` values.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "`capacity` must be non-zero");
+    let shared = Rc::new(Shared {
+        state: RefCell::new(State {
+            buffer: VecDeque::with_capacity(capacity),
+            head: 0,
+            capacity,
+            senders: 1,
+            receivers: 1,
+        }),
+        waiters: WaitQueue::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver {
+            shared,
+            next: 0,
+            key: None,
+        },
+    )
+}
+
+struct Shared<T> {
+    state: RefCell<State<T>>,
+    waiters: WaitQueue,
+}
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    head: u64,
+    capacity: usize,
+    senders: usize,
+    receivers: usize,
+}
+
+impl<T> State<T> {
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+}
+
+/// Sends values to all associated [`Receiver`]s.
+pub struct Sender<T> {
+    shared: Rc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Send a value to all receivers.
+    ///
+    /// Returns the number of receivers, or the value in `Err` if there are no receivers.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let mut s = self.shared.state.borrow_mut();
+        if s.receivers == 0 {
+            return Err(SendError(value));
+        }
+        if s.buffer.len() == s.capacity {
+            s.buffer.pop_front();
+            s.head += 1;
+        }
+        s.buffer.push_back(value);
+        let receivers = s.receivers;
+        drop(s);
+        self.shared.waiters.wake_all();
+        Ok(receivers)
+    }
+
+    /// Create a new receiver that receives values sent after this call.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut s = self.shared.state.borrow_mut();
+        s.receivers += 1;
+        Receiver {
+            shared: self.shared.clone(),
+            next: s.tail(),
+            key: None,
+        }
+    }
+
+    /// Returns the number of receivers.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.borrow().receivers
+    }
+}
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.borrow_mut().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut s = self.shared.state.borrow_mut();
+        s.senders -= 1;
+        if s.senders == 0 {
+            drop(s);
+            self.shared.waiters.wake_all();
+        }
+    }
+}
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// Receives values from the associated [`Sender`]s.
+pub struct Receiver<T> {
+    shared: Rc<Shared<T>>,
+    next: u64,
+    key: Option<u64>,
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receive the next value.
+    ///
+    /// Returns [`RecvError::Lagged`] if values were dropped before this receiver saw them,
+    /// after which receiving continues from the oldest value in the channel.
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Try to receive the next value without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let s = self.shared.state.borrow();
+        if self.next < s.head {
+            let lagged = s.head - self.next;
+            self.next = s.head;
+            return Err(TryRecvError::Lagged(lagged));
+        }
+        if let Some(value) = s.buffer.get((self.next - s.head) as usize) {
+            self.next += 1;
+            Ok(value.clone())
+        } else if s.senders == 0 {
+            Err(TryRecvError::Closed)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+        poll_budget(cx, |cx| {
+            let e = match self.try_recv() {
+                Ok(value) => Ok(value),
+                Err(TryRecvError::Lagged(n)) => Err(RecvError::Lagged(n)),
+                Err(TryRecvError::Closed) => Err(RecvError::Closed),
+                Err(TryRecvError::Empty) => {
+                    self.shared.waiters.register(&mut self.key, cx);
+                    return Poll::Pending;
+                }
+            };
+            self.shared.waiters.remove(&mut self.key);
+            Poll::Ready(e)
+        })
+    }
+
+    /// Create a new receiver that receives values sent after this call.
+    pub fn resubscribe(&self) -> Self {
+        let mut s = self.shared.state.borrow_mut();
+        s.receivers += 1;
+        Self {
+            shared: self.shared.clone(),
+            next: s.tail(),
+            key: None,
+        }
+    }
+}
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.waiters.remove(&mut self.key);
+        self.shared.state.borrow_mut().receivers -= 1;
+    }
+}
+
+/// Yields each value, or [`RecvError::Lagged`] if values were missed.
+///
+/// The stream ends when all senders have been dropped and all values have been received.
+impl<T: Clone> Stream for Receiver<T> {
+    type Item = Result<T, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.get_mut().poll_recv(cx) {
+            Poll::Ready(Err(RecvError::Closed)) => Poll::Ready(None),
+            Poll::Ready(r) => Poll::Ready(Some(r)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Sender::send`] when there are no receivers.
+///
+/// Contains the value that could not be sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Receiver::recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// All senders have been dropped and all values have been received.
+    Closed,
+
+    /// The receiver fell behind and the given number of values were dropped.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "channel closed"),
+            Self::Lagged(n) => write!(f, "channel lagged by {n}"),
+        }
+    }
+}
+impl Error for RecvError {}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// There is no new value.
+    Empty,
+
+    /// All senders have been dropped and all values have been received.
+    Closed,
+
+    /// The receiver fell behind and the given number of values were dropped.
+    Lagged(u64),
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "channel empty"),
+            Self::Closed => write!(f, "channel closed"),
+            Self::Lagged(n) => write!(f, "channel lagged by {n}"),
+        }
+    }
+}
+impl Error for TryRecvError {}
@@ -0,0 +1,493 @@
+//! A multi-producer, single-consumer queue for sending values between tasks.
+//!
+//! # Examples
+//!
+//! ```
+//! use rt_local_core::{spawn_local, sync::mpsc};
+//!
+//! # rt_local_core::runtime::blocking::run(async {
+//! let (tx, mut rx) = mpsc::channel(2);
+//! spawn_local(async move {
+//!     for i in 0..5 {
+//!         tx.send(i).await.unwrap();
+//!     }
+//! })
+//! .detach();
+//! let mut sum = 0;
+//! while let Some(value) = rx.recv().await {
+//!     sum += value;
+//! }
+//! assert_eq!(sum, 10);
+//! # });
+//! ```
+use super::wait_queue::WaitQueue;
+use crate::coop::poll_budget;
+use futures_core::Stream;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::poll_fn,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// Create a bounded channel that can hold at most `capacity` values.
+///
+/// [`Sender::send`] waits until there is space in the channel.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "`capacity` must be non-zero");
+    let chan = Chan::new(Some(capacity));
+    (Sender { chan: chan.clone() }, Receiver(Rx(chan)))
+}
+
+/// Create an unbounded channel.
+pub fn unbounded_channel<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let chan = Chan::new(None);
+    (
+        UnboundedSender { chan: chan.clone() },
+        UnboundedReceiver(Rx(chan)),
+    )
+}
+
+struct Chan<T> {
+    state: RefCell<State<T>>,
+    send_waiters: WaitQueue,
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    capacity: Option<usize>,
+    senders: usize,
+    rx_closed: bool,
+    rx_waker: Option<Waker>,
+}
+
+impl<T> State<T> {
+    fn has_capacity(&self) -> bool {
+        match self.capacity {
+            Some(c) => self.queue.len() < c,
+            None => true,
+        }
+    }
+}
+
+impl<T> Chan<T> {
+    fn new(capacity: Option<usize>) -> Rc<Self> {
+        Rc::new(Self {
+            state: RefCell::new(State {
+                queue: VecDeque::new(),
+                capacity,
+                senders: 1,
+                rx_closed: false,
+                rx_waker: None,
+            }),
+            send_waiters: WaitQueue::new(),
+        })
+    }
+    fn push(&self, value: T) {
+        let waker = {
+            let mut s = self.state.borrow_mut();
+            s.queue.push_back(value);
+            s.rx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let s = self.state.borrow();
+        if s.rx_closed {
+            return Err(TrySendError::Closed(value));
+        }
+        if !s.has_capacity() || !self.send_waiters.is_first(None) {
+            return Err(TrySendError::Full(value));
+        }
+        drop(s);
+        self.push(value);
+        Ok(())
+    }
+    fn poll_send(
+        &self,
+        key: &mut Option<u64>,
+        value: &mut Option<T>,
+        cx: &Context<'_>,
+    ) -> Poll<Result<(), SendError<T>>> {
+        let s = self.state.borrow();
+        if s.rx_closed {
+            drop(s);
+            self.send_waiters.remove(key);
+            return Poll::Ready(Err(SendError(value.take().unwrap())));
+        }
+        if s.has_capacity() && self.send_waiters.is_first(*key) {
+            drop(s);
+            self.send_waiters.remove(key);
+            self.push(value.take().unwrap());
+            if self.state.borrow().has_capacity() {
+                self.send_waiters.wake_first();
+            }
+            return Poll::Ready(Ok(()));
+        }
+        self.send_waiters.register(key, cx);
+        Poll::Pending
+    }
+    fn is_closed(&self) -> bool {
+        self.state.borrow().rx_closed
+    }
+    fn drop_sender(&self) {
+        let waker = {
+            let mut s = self.state.borrow_mut();
+            s.senders -= 1;
+            if s.senders == 0 {
+                s.rx_waker.take()
+            } else {
+                None
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+struct SendWaiter<'a, T> {
+    chan: &'a Chan<T>,
+    key: Option<u64>,
+}
+
+impl<T> Drop for SendWaiter<'_, T> {
+    fn drop(&mut self) {
+        if self.chan.send_waiters.remove(&mut self.key) {
+            let s = self.chan.state.borrow();
+            if s.has_capacity() || s.rx_closed {
+                drop(s);
+                self.chan.send_waiters.wake_first();
+            }
+        }
+    }
+}
+
+/// Sends values to the associated [`Receiver`].
+///
+/// Created by [`channel`].
+pub struct Sender<T> {
+    chan: Rc<Chan<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Send a value, waiting until there is space in the channel.
+    ///
+    /// Senders waiting for space are served in the order they started waiting.
+    /// If the future is dropped before completion, the value is not sent.
+    ///
+    /// Returns the value in `Err` if the receiver has been dropped or closed.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut waiter = SendWaiter {
+            chan: &self.chan,
+            key: None,
+        };
+        let mut value = Some(value);
+        poll_fn(|cx| waiter.chan.poll_send(&mut waiter.key, &mut value, cx)).await
+    }
+
+    /// Try to send a value without waiting.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.chan.try_send(value)
+    }
+
+    /// Returns `true` if the receiver has been dropped or closed.
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+}
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.chan.state.borrow_mut().senders += 1;
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// Sends values to the associated [`UnboundedReceiver`].
+///
+/// Created by [`unbounded_channel`].
+pub struct UnboundedSender<T> {
+    chan: Rc<Chan<T>>,
+}
+
+impl<T> UnboundedSender<T> {
+    /// Send a value without waiting.
+    ///
+    /// Returns the value in `Err` if the receiver has been dropped or closed.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.chan.is_closed() {
+            return Err(SendError(value));
+        }
+        self.chan.push(value);
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver has been dropped or closed.
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+}
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        self.chan.state.borrow_mut().senders += 1;
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+impl<T> Drop for UnboundedSender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+impl<T> fmt::Debug for UnboundedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedSender").finish_non_exhaustive()
+    }
+}
+
+struct Rx<T>(Rc<Chan<T>>);
+
+impl<T> Rx<T> {
+    fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut s = self.0.state.borrow_mut();
+        if let Some(value) = s.queue.pop_front() {
+            let notify = s.capacity.is_some();
+            drop(s);
+            if notify {
+                self.0.send_waiters.wake_first();
+            }
+            Ok(value)
+        } else if s.senders == 0 || s.rx_closed {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        poll_budget(cx, |cx| match self.try_recv() {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => {
+                self.0.state.borrow_mut().rx_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+    }
+    fn close(&mut self) {
+        self.0.state.borrow_mut().rx_closed = true;
+        self.0.send_waiters.wake_all();
+    }
+    fn len(&self) -> usize {
+        self.0.state.borrow().queue.len()
+    }
+}
+impl<T> Drop for Rx<T> {
+    fn drop(&mut self) {
+        self.close();
+        let queue = std::mem::take(&mut self.0.state.borrow_mut().queue);
+        drop(queue);
+    }
+}
+
+/// Receives values from the associated [`Sender`]s.
+///
+/// Created by [`channel`].
+pub struct Receiver<T>(Rx<T>);
+
+impl<T> Receiver<T> {
+    /// Receive the next value.
+    ///
+    /// Returns `None` if all senders have been dropped and the channel is empty, or if the channel is closed and empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Polls to receive the next value.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.0.poll_recv(cx)
+    }
+
+    /// Try to receive the next value without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.0.try_recv()
+    }
+
+    /// Prevent senders from sending more values.
+    ///
+    /// Values already in the channel can still be received.
+    pub fn close(&mut self) {
+        self.0.close()
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the channel contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// Receives values from the associated [`UnboundedSender`]s.
+///
+/// Created by [`unbounded_channel`].
+pub struct UnboundedReceiver<T>(Rx<T>);
+
+impl<T> UnboundedReceiver<T> {
+    /// Receive the next value.
+    ///
+    /// Returns `None` if all senders have been dropped and the channel is empty, or if the channel is closed and empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Polls to receive the next value.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.0.poll_recv(cx)
+    }
+
+    /// Try to receive the next value without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.0.try_recv()
+    }
+
+    /// Prevent senders from sending more values.
+    ///
+    /// Values already in the channel can still be received.
+    pub fn close(&mut self) {
+        self.0.close()
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the channel contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+impl<T> Stream for UnboundedReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+impl<T> fmt::Debug for UnboundedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedReceiver").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Sender::send`] and [`UnboundedSender::send`] when the receiver has been dropped or closed.
+///
+/// Contains the value that could not be sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Sender::try_send`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full.
+    Full(T),
+
+    /// The receiver has been dropped or closed.
+    Closed(T),
+}
+
+impl<T> TrySendError<T> {
+    /// Consumes the error, returning the value that could not be sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(value) | Self::Closed(value) => value,
+        }
+    }
+}
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => write!(f, "Full(..)"),
+            Self::Closed(_) => write!(f, "Closed(..)"),
+        }
+    }
+}
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => write!(f, "channel full"),
+            Self::Closed(_) => write!(f, "channel closed"),
+        }
+    }
+}
+impl<T> Error for TrySendError<T> {}
+
+/// Error returned by [`Receiver::try_recv`] and [`UnboundedReceiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is empty.
+    Empty,
+
+    /// All senders have been dropped and the channel is empty.
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "channel empty"),
+            Self::Disconnected => write!(f, "channel disconnected"),
+        }
+    }
+}
+impl Error for TryRecvError {}
@@ -0,0 +1,119 @@
+use super::semaphore::LocalSemaphore;
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// An asynchronous mutex for tasks running on the same thread.
+///
+/// Unlike [`RefCell`](std::cell::RefCell), the guard can be held across `.await`;
+/// other tasks calling [`LocalMutex::lock`] wait until it is dropped.
+/// Tasks waiting for the lock are served in the order they started waiting.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::{spawn_local, sync::LocalMutex, yield_now};
+/// use std::rc::Rc;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let state = Rc::new(LocalMutex::new(Vec::new()));
+/// let task = spawn_local({
+///     let state = state.clone();
+///     async move {
+///         let mut state = state.lock().await;
+///         yield_now().await;
+///         state.push(1);
+///     }
+/// });
+/// task.await;
+/// assert_eq!(*state.lock().await, [1]);
+/// # });
+/// ```
+pub struct LocalMutex<T: ?Sized> {
+    sem: LocalSemaphore,
+    value: UnsafeCell<T>,
+}
+
+impl<T> LocalMutex<T> {
+    /// Create a new mutex holding `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            sem: LocalSemaphore::new(1),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the mutex, returning the value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> LocalMutex<T> {
+    /// Lock the mutex, waiting until it is available.
+    pub async fn lock(&self) -> LocalMutexGuard<'_, T> {
+        self.sem.acquire_raw(1).await;
+        LocalMutexGuard { mutex: self }
+    }
+
+    /// Lock the mutex without waiting.
+    ///
+    /// Returns `None` if the mutex is locked, or if other tasks are waiting for it.
+    pub fn try_lock(&self) -> Option<LocalMutexGuard<'_, T>> {
+        self.sem.try_acquire_raw(1).ok()?;
+        Some(LocalMutexGuard { mutex: self })
+    }
+
+    /// Returns a mutable reference to the value.
+    ///
+    /// No locking is needed since this call borrows the mutex mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+impl<T: Default> Default for LocalMutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LocalMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("LocalMutex");
+        match self.try_lock() {
+            Some(guard) => d.field("value", &&*guard),
+            None => d.field("value", &format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
+
+/// Guard returned by [`LocalMutex::lock`]. The mutex is unlocked when dropped.
+#[must_use = "if unused the mutex will immediately unlock"]
+pub struct LocalMutexGuard<'a, T: ?Sized> {
+    mutex: &'a LocalMutex<T>,
+}
+
+impl<T: ?Sized> Deref for LocalMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+impl<T: ?Sized> DerefMut for LocalMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+impl<T: ?Sized> Drop for LocalMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.sem.add_permits(1);
+    }
+}
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LocalMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
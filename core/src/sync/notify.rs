@@ -0,0 +1,134 @@
+use super::wait_queue::WaitQueue;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Notifies tasks running on the same thread.
+///
+/// [`LocalNotify::notify_one`] wakes the task that started waiting first,
+/// or stores a permit that is consumed by the next call to [`LocalNotify::notified`] if no task is waiting.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::{spawn_local, sync::LocalNotify};
+/// use std::rc::Rc;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let notify = Rc::new(LocalNotify::new());
+/// let task = spawn_local({
+///     let notify = notify.clone();
+///     async move { notify.notified().await }
+/// });
+/// notify.notify_one();
+/// task.await;
+/// # });
+/// ```
+pub struct LocalNotify {
+    permit: Cell<bool>,
+    generation: Cell<u64>,
+    waiters: WaitQueue,
+    notified: RefCell<BTreeSet<u64>>,
+}
+
+impl LocalNotify {
+    /// Create a new `LocalNotify` without a stored permit.
+    pub fn new() -> Self {
+        Self {
+            permit: Cell::new(false),
+            generation: Cell::new(0),
+            waiters: WaitQueue::new(),
+            notified: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Wait for a notification.
+    ///
+    /// The future is notified by [`LocalNotify::notify_waiters`] called after this call,
+    /// or by [`LocalNotify::notify_one`]. If the future is dropped after receiving a notification from
+    /// [`LocalNotify::notify_one`], the notification is passed on to the next waiter.
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            generation: self.generation.get(),
+            key: None,
+        }
+    }
+
+    /// Notify the first waiting task, or store a permit if no task is waiting.
+    pub fn notify_one(&self) {
+        match self.waiters.pop_first() {
+            Some(key) => {
+                self.notified.borrow_mut().insert(key);
+            }
+            None => self.permit.set(true),
+        }
+    }
+
+    /// Notify all waiting tasks without storing a permit.
+    pub fn notify_waiters(&self) {
+        self.generation.set(self.generation.get() + 1);
+        self.waiters.take_all();
+    }
+}
+impl Default for LocalNotify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl fmt::Debug for LocalNotify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalNotify")
+            .field("permit", &self.permit.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`LocalNotify::notified`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Notified<'a> {
+    notify: &'a LocalNotify,
+    generation: u64,
+    key: Option<u64>,
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let n = self.notify;
+        if let Some(key) = self.key {
+            if n.notified.borrow_mut().remove(&key) {
+                self.key = None;
+                return Poll::Ready(());
+            }
+        }
+        if n.generation.get() != self.generation {
+            self.key = None;
+            return Poll::Ready(());
+        }
+        if self.key.is_none() && n.permit.replace(false) {
+            return Poll::Ready(());
+        }
+        n.waiters.register(&mut self.key, cx);
+        Poll::Pending
+    }
+}
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        let Some(key) = self.key else {
+            return;
+        };
+        let n = self.notify;
+        if n.notified.borrow_mut().remove(&key) {
+            n.notify_one();
+        } else {
+            n.waiters.remove(&mut self.key);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! A channel for sending a single value between tasks.
+//!
+//! # Examples
+//!
+//! ```
+//! use rt_local_core::{spawn_local, sync::oneshot};
+//!
+//! # rt_local_core::runtime::blocking::run(async {
+//! let (tx, rx) = oneshot::channel();
+//! spawn_local(async move {
+//!     tx.send(10).unwrap();
+//! })
+//! .detach();
+//! assert_eq!(rx.await, Ok(10));
+//! # });
+//! ```
+use crate::coop::poll_budget;
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt,
+    future::{poll_fn, Future},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// Create a new oneshot channel.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(RefCell::new(Shared {
+        value: None,
+        tx_closed: false,
+        rx_closed: false,
+        tx_waker: None,
+        rx_waker: None,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    value: Option<T>,
+    tx_closed: bool,
+    rx_closed: bool,
+    tx_waker: Option<Waker>,
+    rx_waker: Option<Waker>,
+}
+
+/// Sends a value to the associated [`Receiver`].
+pub struct Sender<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+impl<T> Sender<T> {
+    /// Send a value to the receiver.
+    ///
+    /// Returns the value in `Err` if the receiver has been dropped or closed.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let mut s = self.shared.borrow_mut();
+        if s.rx_closed {
+            return Err(value);
+        }
+        s.value = Some(value);
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver has been dropped or closed.
+    pub fn is_closed(&self) -> bool {
+        self.shared.borrow().rx_closed
+    }
+
+    /// Wait until the receiver is dropped or closed.
+    pub async fn closed(&mut self) {
+        poll_fn(|cx| self.poll_closed(cx)).await
+    }
+
+    /// Polls whether the receiver has been dropped or closed.
+    pub fn poll_closed(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut s = self.shared.borrow_mut();
+        if s.rx_closed {
+            Poll::Ready(())
+        } else {
+            s.tx_waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut s = self.shared.borrow_mut();
+            s.tx_closed = true;
+            s.tx_waker = None;
+            s.rx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// Receives a value from the associated [`Sender`].
+///
+/// Awaiting the receiver returns the value, or [`RecvError`] if the sender was dropped without sending a value.
+pub struct Receiver<T> {
+    shared: Rc<RefCell<Shared<T>>>,
+}
+
+impl<T> Receiver<T> {
+    /// Try to receive the value without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut s = self.shared.borrow_mut();
+        if let Some(value) = s.value.take() {
+            Ok(value)
+        } else if s.tx_closed {
+            Err(TryRecvError::Closed)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Prevent the sender from sending a value.
+    ///
+    /// A value sent before this call can still be received.
+    pub fn close(&mut self) {
+        let waker = {
+            let mut s = self.shared.borrow_mut();
+            s.rx_closed = true;
+            s.tx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        poll_budget(cx, |cx| match this.try_recv() {
+            Ok(value) => Poll::Ready(Ok(value)),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError(()))),
+            Err(TryRecvError::Empty) => {
+                this.shared.borrow_mut().rx_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+    }
+}
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by awaiting [`Receiver`] when the sender was dropped without sending a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+impl Error for RecvError {}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The value has not been sent yet.
+    Empty,
+
+    /// The sender was dropped without sending a value.
+    Closed,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "channel empty"),
+            Self::Closed => write!(f, "channel closed"),
+        }
+    }
+}
+impl Error for TryRecvError {}
@@ -0,0 +1,150 @@
+use super::semaphore::LocalSemaphore;
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+const MAX_READS: usize = usize::MAX >> 3;
+
+/// An asynchronous reader-writer lock for tasks running on the same thread.
+///
+/// Readers and writers are served in the order they started waiting,
+/// so a waiting writer is not starved by readers that arrive after it.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::sync::LocalRwLock;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let lock = LocalRwLock::new(1);
+/// {
+///     let r1 = lock.read().await;
+///     let r2 = lock.read().await;
+///     assert_eq!(*r1 + *r2, 2);
+/// }
+/// *lock.write().await += 1;
+/// assert_eq!(*lock.read().await, 2);
+/// # });
+/// ```
+pub struct LocalRwLock<T: ?Sized> {
+    sem: LocalSemaphore,
+    value: UnsafeCell<T>,
+}
+
+impl<T> LocalRwLock<T> {
+    /// Create a new lock holding `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            sem: LocalSemaphore::new(MAX_READS),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the lock, returning the value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> LocalRwLock<T> {
+    /// Lock for reading, waiting until no writer holds or is waiting ahead for the lock.
+    pub async fn read(&self) -> LocalRwLockReadGuard<'_, T> {
+        self.sem.acquire_raw(1).await;
+        LocalRwLockReadGuard { lock: self }
+    }
+
+    /// Lock for reading without waiting.
+    pub fn try_read(&self) -> Option<LocalRwLockReadGuard<'_, T>> {
+        self.sem.try_acquire_raw(1).ok()?;
+        Some(LocalRwLockReadGuard { lock: self })
+    }
+
+    /// Lock for writing, waiting until no other guard exists.
+    pub async fn write(&self) -> LocalRwLockWriteGuard<'_, T> {
+        self.sem.acquire_raw(MAX_READS).await;
+        LocalRwLockWriteGuard { lock: self }
+    }
+
+    /// Lock for writing without waiting.
+    pub fn try_write(&self) -> Option<LocalRwLockWriteGuard<'_, T>> {
+        self.sem.try_acquire_raw(MAX_READS).ok()?;
+        Some(LocalRwLockWriteGuard { lock: self })
+    }
+
+    /// Returns a mutable reference to the value.
+    ///
+    /// No locking is needed since this call borrows the lock mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+impl<T: Default> Default for LocalRwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LocalRwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("LocalRwLock");
+        match self.try_read() {
+            Some(guard) => d.field("value", &&*guard),
+            None => d.field("value", &format_args!("<locked>")),
+        };
+        d.finish()
+    }
+}
+
+/// Guard returned by [`LocalRwLock::read`].
+#[must_use = "if unused the lock will immediately unlock"]
+pub struct LocalRwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a LocalRwLock<T>,
+}
+
+impl<T: ?Sized> Deref for LocalRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+impl<T: ?Sized> Drop for LocalRwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.sem.add_permits(1);
+    }
+}
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LocalRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Guard returned by [`LocalRwLock::write`].
+#[must_use = "if unused the lock will immediately unlock"]
+pub struct LocalRwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a LocalRwLock<T>,
+}
+
+impl<T: ?Sized> Deref for LocalRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+impl<T: ?Sized> DerefMut for LocalRwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+impl<T: ?Sized> Drop for LocalRwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.sem.add_permits(MAX_READS);
+    }
+}
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LocalRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,211 @@
+use super::wait_queue::WaitQueue;
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt,
+    future::poll_fn,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+/// A semaphore for tasks running on the same thread.
+///
+/// Tasks waiting for permits are served in the order they started waiting.
+/// Dropping an acquire future before it completes removes it from the queue without losing any permits.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::{spawn_local, sync::LocalSemaphore};
+/// use std::rc::Rc;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let downloads = Rc::new(LocalSemaphore::new(2));
+/// let mut tasks = Vec::new();
+/// for _ in 0..5 {
+///     let downloads = downloads.clone();
+///     tasks.push(spawn_local(async move {
+///         let _permit = downloads.acquire().await;
+///         // at most 2 tasks run here at the same time.
+///     }));
+/// }
+/// for task in tasks {
+///     task.await;
+/// }
+/// # });
+/// ```
+pub struct LocalSemaphore {
+    permits: Cell<usize>,
+    waiters: WaitQueue,
+}
+
+impl LocalSemaphore {
+    /// Create a semaphore with the given number of permits.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits: Cell::new(permits),
+            waiters: WaitQueue::new(),
+        }
+    }
+
+    /// Returns the number of permits currently available.
+    pub fn available_permits(&self) -> usize {
+        self.permits.get()
+    }
+
+    /// Add `n` permits to the semaphore.
+    pub fn add_permits(&self, n: usize) {
+        self.permits.set(self.permits.get() + n);
+        self.waiters.wake_first();
+    }
+
+    /// Acquire a permit, waiting until one is available.
+    pub async fn acquire(&self) -> LocalSemaphorePermit<'_> {
+        self.acquire_many(1).await
+    }
+
+    /// Acquire `n` permits, waiting until they are available.
+    pub async fn acquire_many(&self, n: usize) -> LocalSemaphorePermit<'_> {
+        self.acquire_raw(n).await;
+        LocalSemaphorePermit { sem: self, n }
+    }
+
+    /// Acquire a permit without waiting.
+    pub fn try_acquire(&self) -> Result<LocalSemaphorePermit<'_>, TryAcquireError> {
+        self.try_acquire_many(1)
+    }
+
+    /// Acquire `n` permits without waiting.
+    ///
+    /// Fails if there are not enough permits, or if other tasks are waiting for permits.
+    pub fn try_acquire_many(&self, n: usize) -> Result<LocalSemaphorePermit<'_>, TryAcquireError> {
+        self.try_acquire_raw(n)?;
+        Ok(LocalSemaphorePermit { sem: self, n })
+    }
+
+    /// Acquire a permit that holds a reference count of the semaphore, waiting until one is available.
+    pub async fn acquire_owned(self: Rc<Self>) -> OwnedLocalSemaphorePermit {
+        self.acquire_many_owned(1).await
+    }
+
+    /// Acquire `n` permits that hold a reference count of the semaphore, waiting until they are available.
+    pub async fn acquire_many_owned(self: Rc<Self>, n: usize) -> OwnedLocalSemaphorePermit {
+        self.acquire_raw(n).await;
+        OwnedLocalSemaphorePermit { sem: self, n }
+    }
+
+    pub(crate) async fn acquire_raw(&self, n: usize) {
+        let mut waiter = Waiter {
+            sem: self,
+            key: None,
+        };
+        poll_fn(|cx| waiter.sem.poll_acquire(n, &mut waiter.key, cx)).await
+    }
+    pub(crate) fn try_acquire_raw(&self, n: usize) -> Result<(), TryAcquireError> {
+        if self.permits.get() >= n && self.waiters.is_first(None) {
+            self.permits.set(self.permits.get() - n);
+            Ok(())
+        } else {
+            Err(TryAcquireError(()))
+        }
+    }
+    fn poll_acquire(&self, n: usize, key: &mut Option<u64>, cx: &Context<'_>) -> Poll<()> {
+        if self.permits.get() >= n && self.waiters.is_first(*key) {
+            self.permits.set(self.permits.get() - n);
+            self.waiters.remove(key);
+            if self.permits.get() > 0 {
+                self.waiters.wake_first();
+            }
+            Poll::Ready(())
+        } else {
+            self.waiters.register(key, cx);
+            Poll::Pending
+        }
+    }
+}
+impl fmt::Debug for LocalSemaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSemaphore")
+            .field("permits", &self.permits.get())
+            .finish_non_exhaustive()
+    }
+}
+
+struct Waiter<'a> {
+    sem: &'a LocalSemaphore,
+    key: Option<u64>,
+}
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        if self.sem.waiters.remove(&mut self.key) && self.sem.permits.get() > 0 {
+            self.sem.waiters.wake_first();
+        }
+    }
+}
+
+/// Permits acquired from [`LocalSemaphore`]. The permits are returned when dropped.
+#[must_use]
+pub struct LocalSemaphorePermit<'a> {
+    sem: &'a LocalSemaphore,
+    n: usize,
+}
+
+impl LocalSemaphorePermit<'_> {
+    /// Consume the permit without returning it to the semaphore.
+    pub fn forget(mut self) {
+        self.n = 0;
+    }
+}
+impl Drop for LocalSemaphorePermit<'_> {
+    fn drop(&mut self) {
+        if self.n > 0 {
+            self.sem.add_permits(self.n);
+        }
+    }
+}
+impl fmt::Debug for LocalSemaphorePermit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSemaphorePermit")
+            .field("permits", &self.n)
+            .finish()
+    }
+}
+
+/// Permits acquired from [`LocalSemaphore::acquire_owned`]. The permits are returned when dropped.
+#[must_use]
+pub struct OwnedLocalSemaphorePermit {
+    sem: Rc<LocalSemaphore>,
+    n: usize,
+}
+
+impl OwnedLocalSemaphorePermit {
+    /// Consume the permit without returning it to the semaphore.
+    pub fn forget(mut self) {
+        self.n = 0;
+    }
+}
+impl Drop for OwnedLocalSemaphorePermit {
+    fn drop(&mut self) {
+        if self.n > 0 {
+            self.sem.add_permits(self.n);
+        }
+    }
+}
+impl fmt::Debug for OwnedLocalSemaphorePermit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedLocalSemaphorePermit")
+            .field("permits", &self.n)
+            .finish()
+    }
+}
+
+/// Error returned by [`LocalSemaphore::try_acquire`] when no permits are available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TryAcquireError(());
+
+impl fmt::Display for TryAcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no permits available")
+    }
+}
+impl Error for TryAcquireError {}
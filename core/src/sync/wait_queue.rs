@@ -0,0 +1,87 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    mem::take,
+    task::{Context, Waker},
+};
+
+/// FIFO queue of waiting futures.
+///
+/// A waiter stays in the queue after it is woken, until it is removed by [`WaitQueue::remove`].
+/// This allows a waiter that was dropped after being woken to pass the notification to the next waiter.
+#[derive(Default)]
+pub(crate) struct WaitQueue(RefCell<Inner>);
+
+#[derive(Default)]
+struct Inner {
+    waiters: BTreeMap<u64, Waker>,
+    next_key: u64,
+}
+
+impl WaitQueue {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the waker of `cx`, or update it if `key` is already registered.
+    pub(crate) fn register(&self, key: &mut Option<u64>, cx: &Context<'_>) {
+        let mut inner = self.0.borrow_mut();
+        let inner = &mut *inner;
+        let waker = cx.waker();
+        match key.and_then(|key| inner.waiters.get_mut(&key)) {
+            Some(w) if w.will_wake(waker) => {}
+            Some(w) => *w = waker.clone(),
+            None => {
+                let k = inner.next_key;
+                inner.next_key += 1;
+                inner.waiters.insert(k, waker.clone());
+                *key = Some(k);
+            }
+        }
+    }
+
+    /// Remove `key` from the queue. Returns `true` if it was the first waiter.
+    pub(crate) fn remove(&self, key: &mut Option<u64>) -> bool {
+        let Some(key) = key.take() else {
+            return false;
+        };
+        let mut inner = self.0.borrow_mut();
+        let is_first = inner.waiters.keys().next() == Some(&key);
+        inner.waiters.remove(&key);
+        is_first
+    }
+
+    /// Returns `true` if `key` is the first waiter, or if `key` is `None` and there are no waiters.
+    pub(crate) fn is_first(&self, key: Option<u64>) -> bool {
+        self.0.borrow().waiters.keys().next().copied() == key
+    }
+
+    pub(crate) fn wake_first(&self) {
+        let waker = self.0.borrow().waiters.values().next().cloned();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Remove the first waiter and wake it. Returns its key.
+    pub(crate) fn pop_first(&self) -> Option<u64> {
+        let (key, waker) = self.0.borrow_mut().waiters.pop_first()?;
+        waker.wake();
+        Some(key)
+    }
+
+    /// Remove all waiters and wake them.
+    pub(crate) fn take_all(&self) {
+        let waiters = take(&mut self.0.borrow_mut().waiters);
+        for waker in waiters.into_values() {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn wake_all(&self) {
+        let wakers: Vec<_> = self.0.borrow().waiters.values().cloned().collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
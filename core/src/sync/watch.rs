@@ -0,0 +1,280 @@
+//! A single-producer, multi-consumer channel that retains only the latest value.
+//!
+//! # Examples
+//!
+//! ```
+//! use rt_local_core::{spawn_local, sync::watch};
+//!
+//! # rt_local_core::runtime::blocking::run(async {
+//! let (tx, mut rx) = watch::channel("loading");
+//! spawn_local(async move {
+//!     tx.send("ready").unwrap();
+//! })
+//! .detach();
+//! rx.changed().await.unwrap();
+//! assert_eq!(*rx.borrow_and_update(), "ready");
+//! # });
+//! ```
+use super::wait_queue::WaitQueue;
+use crate::coop::poll_budget;
+use futures_core::Stream;
+use std::{
+    cell::{Ref, RefCell},
+    error::Error,
+    fmt,
+    future::poll_fn,
+    mem::replace,
+    ops::Deref,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+/// Create a new watch channel with an initial value.
+///
+/// The initial value is considered seen by the returned receiver.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(Shared {
+        value: RefCell::new(init),
+        state: RefCell::new(State {
+            version: 0,
+            receivers: 1,
+            tx_closed: false,
+        }),
+        waiters: WaitQueue::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver {
+            shared,
+            version: 0,
+            key: None,
+        },
+    )
+}
+
+struct Shared<T> {
+    value: RefCell<T>,
+    state: RefCell<State>,
+    waiters: WaitQueue,
+}
+
+struct State {
+    version: u64,
+    receivers: usize,
+    tx_closed: bool,
+}
+
+impl<T> Shared<T> {
+    fn notify(&self) {
+        self.state.borrow_mut().version += 1;
+        self.waiters.wake_all();
+    }
+}
+
+/// Sends values to the associated [`Receiver`]s.
+pub struct Sender<T> {
+    shared: Rc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Replace the value and notify all receivers.
+    ///
+    /// Returns the value in `Err` if there are no receivers.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.is_closed() {
+            return Err(SendError(value));
+        }
+        self.send_replace(value);
+        Ok(())
+    }
+
+    /// Replace the value and notify all receivers, returning the previous value.
+    ///
+    /// Unlike [`Sender::send`], the value is replaced even if there are no receivers.
+    pub fn send_replace(&self, value: T) -> T {
+        let old = replace(&mut *self.shared.value.borrow_mut(), value);
+        self.shared.notify();
+        old
+    }
+
+    /// Modify the value in place and notify all receivers.
+    pub fn send_modify(&self, f: impl FnOnce(&mut T)) {
+        f(&mut self.shared.value.borrow_mut());
+        self.shared.notify();
+    }
+
+    /// Returns a reference to the current value.
+    ///
+    /// The value cannot be sent while the returned reference is held.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.shared.value.borrow()
+    }
+
+    /// Create a new receiver. The current value is considered seen by the returned receiver.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut s = self.shared.state.borrow_mut();
+        s.receivers += 1;
+        Receiver {
+            shared: self.shared.clone(),
+            version: s.version,
+            key: None,
+        }
+    }
+
+    /// Returns the number of receivers.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.borrow().receivers
+    }
+
+    /// Returns `true` if all receivers have been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.receiver_count() == 0
+    }
+}
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.state.borrow_mut().tx_closed = true;
+        self.shared.waiters.wake_all();
+    }
+}
+impl<T: fmt::Debug> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("value", &*self.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Receives values from the associated [`Sender`].
+pub struct Receiver<T> {
+    shared: Rc<Shared<T>>,
+    version: u64,
+    key: Option<u64>,
+}
+
+impl<T> Receiver<T> {
+    /// Returns a reference to the current value without marking it as seen.
+    ///
+    /// The value cannot be sent while the returned reference is held.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.shared.value.borrow()
+    }
+
+    /// Returns a reference to the current value and marks it as seen.
+    ///
+    /// The value cannot be sent while the returned reference is held.
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        self.version = self.shared.state.borrow().version;
+        self.shared.value.borrow()
+    }
+
+    /// Returns `true` if the value has changed since it was last seen.
+    ///
+    /// Returns [`RecvError`] if the sender has been dropped.
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        let s = self.shared.state.borrow();
+        if s.tx_closed {
+            Err(RecvError(()))
+        } else {
+            Ok(s.version != self.version)
+        }
+    }
+
+    /// Wait until the value changes, then mark it as seen.
+    ///
+    /// Returns [`RecvError`] if the sender has been dropped.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        poll_fn(|cx| self.poll_changed(cx)).await
+    }
+
+    fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), RecvError>> {
+        poll_budget(cx, |cx| {
+            let s = self.shared.state.borrow();
+            if s.version != self.version {
+                self.version = s.version;
+                drop(s);
+                self.shared.waiters.remove(&mut self.key);
+                Poll::Ready(Ok(()))
+            } else if s.tx_closed {
+                drop(s);
+                self.shared.waiters.remove(&mut self.key);
+                Poll::Ready(Err(RecvError(())))
+            } else {
+                drop(s);
+                self.shared.waiters.register(&mut self.key, cx);
+                Poll::Pending
+            }
+        })
+    }
+}
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.borrow_mut().receivers += 1;
+        Self {
+            shared: self.shared.clone(),
+            version: self.version,
+            key: None,
+        }
+    }
+}
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.waiters.remove(&mut self.key);
+        self.shared.state.borrow_mut().receivers -= 1;
+    }
+}
+
+/// Yields a copy of the value each time it changes.
+///
+/// The stream ends when the sender is dropped.
+impl<T: Clone> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match this.poll_changed(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(Some(this.borrow().deref().clone())),
+            Poll::Ready(Err(_)) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+impl<T: fmt::Debug> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("value", &*self.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`Sender::send`] when there are no receivers.
+///
+/// Contains the value that could not be sent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Receiver::changed`] and [`Receiver::has_changed`] when the sender has been dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+impl Error for RecvError {}
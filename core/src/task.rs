@@ -0,0 +1,323 @@
+//! Types related to spawned tasks.
+use crate::{
+    base_impl::{spawn_local_with, Runtime},
+    Task,
+};
+use std::{
+    any::Any,
+    cell::RefCell,
+    error::Error,
+    fmt,
+    future::Future,
+    panic::Location,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+pub use crate::base_impl::{yield_now, AbortHandle, Join};
+pub use crate::coop::{consume_budget, set_coop_budget};
+pub use crate::join_set::LocalJoinSet;
+pub use crate::task_local::{AccessError, LocalKey, TaskLocalFuture};
+pub use crate::thread_pool::set_max_blocking_threads;
+
+/// Builder to configure a task before spawning it.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::task::Builder;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let task = Builder::new().name("fetch-thumbnails").spawn_local(async {
+///     // ...
+/// });
+/// assert_eq!(task.name(), Some("fetch-thumbnails"));
+/// task.await;
+/// # });
+/// ```
+#[derive(Debug, Default)]
+#[must_use]
+pub struct Builder {
+    name: Option<Arc<str>>,
+    priority: Priority,
+}
+
+impl Builder {
+    /// Create a new builder with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the name of the task.
+    pub fn name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the priority of the task.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Spawn a future on the current thread with this configuration.
+    ///
+    /// See [`spawn_local`](crate::spawn_local) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime is not running.
+    #[must_use]
+    #[track_caller]
+    pub fn spawn_local<F: Future + 'static>(self, future: F) -> Task<F::Output> {
+        let info = TaskInfo::new(self.name, Location::caller(), self.priority);
+        spawn_local_with(future, info)
+    }
+}
+
+/// Priority of a task.
+///
+/// Woken tasks are polled in order of priority.
+/// [`Background`](Priority::Background) tasks are polled only when no [`High`](Priority::High) or [`Normal`](Priority::Normal) task is woken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Polled before other tasks. Suitable for tasks driven by user input.
+    High,
+
+    /// Default priority.
+    #[default]
+    Normal,
+
+    /// Polled only when there are no other tasks to poll. Suitable for indexing, thumbnail decoding, etc.
+    Background,
+}
+
+impl Priority {
+    pub(crate) const COUNT: usize = 3;
+}
+
+/// An opaque id that uniquely identifies a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    fn new() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct TaskInfo {
+    pub(crate) id: TaskId,
+    pub(crate) name: Option<Arc<str>>,
+    pub(crate) location: &'static Location<'static>,
+    pub(crate) priority: Priority,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<TaskInfo>> = const { RefCell::new(None) };
+}
+
+impl TaskInfo {
+    pub(crate) fn new(
+        name: Option<Arc<str>>,
+        location: &'static Location<'static>,
+        priority: Priority,
+    ) -> Self {
+        Self {
+            id: TaskId::new(),
+            name,
+            location,
+            priority,
+        }
+    }
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    pub(crate) fn enter<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<TaskInfo>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|c| *c.borrow_mut() = self.0.take());
+            }
+        }
+        let _restore = Restore(CURRENT.with(|c| c.borrow_mut().replace(self.clone())));
+        f()
+    }
+}
+impl fmt::Display for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {}", self.id)?;
+        if let Some(name) = &self.name {
+            write!(f, " `{name}`")?;
+        }
+        write!(f, " spawned at {}", self.location)
+    }
+}
+
+/// Returns the id of the currently running task.
+///
+/// Returns `None` if called outside of a task spawned by [`spawn_local`](crate::spawn_local).
+pub fn current_id() -> Option<TaskId> {
+    CURRENT.with(|c| c.borrow().as_ref().map(|info| info.id))
+}
+
+/// Returns the name of the currently running task.
+///
+/// Returns `None` if called outside of a task spawned by [`spawn_local`](crate::spawn_local), or if the task has no name.
+pub fn current_name() -> Option<Arc<str>> {
+    CURRENT.with(|c| c.borrow().as_ref().and_then(|info| info.name.clone()))
+}
+
+/// How panics in tasks spawned by [`spawn_local`](crate::spawn_local) are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Catch the panic and store it in the task.
+    ///
+    /// Awaiting the [`Task`](crate::Task) resumes the panic, and [`Task::join`](crate::Task::join) returns it as [`JoinError`].
+    #[default]
+    Catch,
+
+    /// Let the panic unwind out of the runtime.
+    Propagate,
+}
+
+/// Set how panics in tasks spawned on the current thread are handled.
+///
+/// # Panics
+///
+/// Panics if the runtime is not running.
+pub fn set_panic_policy(policy: PanicPolicy) {
+    Runtime::with(|rt| rt.panic_policy = policy)
+}
+
+/// Error returned by [`Join`] when the task panicked or was canceled.
+pub struct JoinError {
+    repr: Repr,
+    info: TaskInfo,
+}
+
+enum Repr {
+    Panic(Box<dyn Any + Send>),
+    Cancelled,
+}
+
+impl JoinError {
+    pub(crate) fn panic(payload: Box<dyn Any + Send>, info: TaskInfo) -> Self {
+        Self {
+            repr: Repr::Panic(payload),
+            info,
+        }
+    }
+    pub(crate) fn cancelled(info: TaskInfo) -> Self {
+        Self {
+            repr: Repr::Cancelled,
+            info,
+        }
+    }
+
+    /// Returns `true` if the task panicked.
+    pub fn is_panic(&self) -> bool {
+        matches!(self.repr, Repr::Panic(_))
+    }
+
+    /// Returns `true` if the task was canceled by [`AbortHandle::abort`].
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.repr, Repr::Cancelled)
+    }
+
+    /// Returns the id of the task.
+    pub fn id(&self) -> TaskId {
+        self.info.id
+    }
+
+    /// Returns the name of the task.
+    pub fn name(&self) -> Option<&str> {
+        self.info.name()
+    }
+
+    /// Returns the location where the task was spawned.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.info.location
+    }
+
+    /// Returns the payload of the panic, or `None` if the task did not panic.
+    pub fn panic_payload(&self) -> Option<&(dyn Any + Send)> {
+        match &self.repr {
+            Repr::Panic(payload) => Some(&**payload),
+            Repr::Cancelled => None,
+        }
+    }
+
+    /// Consumes the error, returning the payload of the panic.
+    ///
+    /// The returned value can be passed to [`std::panic::resume_unwind`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the task did not panic.
+    pub fn into_panic(self) -> Box<dyn Any + Send> {
+        self.try_into_panic()
+            .unwrap_or_else(|e| panic!("`into_panic` called on `{e}`"))
+    }
+
+    /// Consumes the error, returning the payload of the panic if the task panicked.
+    pub fn try_into_panic(self) -> Result<Box<dyn Any + Send>, JoinError> {
+        match self.repr {
+            Repr::Panic(payload) => Ok(payload),
+            Repr::Cancelled => Err(self),
+        }
+    }
+
+    fn message(&self) -> Option<&str> {
+        let payload = self.panic_payload()?;
+        if let Some(s) = payload.downcast_ref::<&'static str>() {
+            Some(s)
+        } else {
+            payload.downcast_ref::<String>().map(|s| s.as_str())
+        }
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Panic(_) => f
+                .debug_struct("JoinError::Panic")
+                .field("id", &self.info.id)
+                .field("name", &self.info.name())
+                .field("location", &self.info.location)
+                .field("message", &self.message())
+                .finish_non_exhaustive(),
+            Repr::Cancelled => f
+                .debug_struct("JoinError::Cancelled")
+                .field("id", &self.info.id)
+                .field("name", &self.info.name())
+                .field("location", &self.info.location)
+                .finish(),
+        }
+    }
+}
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Panic(_) => {
+                write!(f, "{} panicked", self.info)?;
+                if let Some(message) = self.message() {
+                    write!(f, ": {message}")?;
+                }
+                Ok(())
+            }
+            Repr::Cancelled => write!(f, "{} was cancelled", self.info),
+        }
+    }
+}
+impl Error for JoinError {}
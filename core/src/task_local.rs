@@ -0,0 +1,232 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    error::Error,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    mem::swap,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+/// Declare task-local keys of type [`LocalKey`].
+///
+/// # Examples
+///
+/// ```
+/// rt_local_core::task_local! {
+///     static REQUEST_ID: u32;
+/// }
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// REQUEST_ID
+///     .scope(10, async {
+///         assert_eq!(REQUEST_ID.get(), 10);
+///     })
+///     .await;
+/// # });
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task::LocalKey<$t> = $crate::task::LocalKey::new();
+        $crate::task_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $crate::task_local!($(#[$attr])* $vis static $name: $t;);
+    };
+}
+
+/// A key for task-local data, declared by [`task_local!`](crate::task_local).
+///
+/// A value is set by [`LocalKey::scope`] and is available while the future passed to it is polled.
+pub struct LocalKey<T: 'static> {
+    _id: u8,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    key: *const (),
+    value: Rc<dyn Any>,
+    inherit: bool,
+}
+
+thread_local! {
+    static LOCALS: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            _id: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn id(&'static self) -> *const () {
+        self as *const Self as *const ()
+    }
+
+    /// Set the value of the key while `future` is polled.
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<F> {
+        self.scope_with(value, future, false)
+    }
+
+    /// Set the value of the key while `future` is polled,
+    /// and also for tasks spawned by [`spawn_local`](crate::spawn_local) while `future` is polled.
+    pub fn inherited_scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<F> {
+        self.scope_with(value, future, true)
+    }
+
+    fn scope_with<F: Future>(
+        &'static self,
+        value: T,
+        future: F,
+        inherit: bool,
+    ) -> TaskLocalFuture<F> {
+        TaskLocalFuture {
+            entry: Entry {
+                key: self.id(),
+                value: Rc::new(value),
+                inherit,
+            },
+            future,
+        }
+    }
+
+    /// Set the value of the key while `f` is called.
+    pub fn sync_scope<R>(&'static self, value: T, f: impl FnOnce() -> R) -> R {
+        let entry = Entry {
+            key: self.id(),
+            value: Rc::new(value),
+            inherit: false,
+        };
+        push(entry, f)
+    }
+
+    /// Call `f` with a reference to the value of the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not set.
+    #[track_caller]
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        match self.try_with(f) {
+            Ok(value) => value,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Call `f` with a reference to the value of the key, or returns [`AccessError`] if the value is not set.
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError> {
+        let key = self.id();
+        let value = LOCALS.with(|locals| {
+            locals
+                .borrow()
+                .iter()
+                .rev()
+                .find(|e| e.key == key)
+                .map(|e| e.value.clone())
+        });
+        match value {
+            Some(value) => Ok(f(value.downcast_ref().unwrap())),
+            None => Err(AccessError(())),
+        }
+    }
+
+    /// Returns a copy of the value of the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not set.
+    #[track_caller]
+    pub fn get(&'static self) -> T
+    where
+        T: Clone,
+    {
+        self.with(|value| value.clone())
+    }
+}
+
+impl<T: 'static> fmt::Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKey").finish_non_exhaustive()
+    }
+}
+
+fn push<R>(entry: Entry, f: impl FnOnce() -> R) -> R {
+    struct Pop(usize);
+    impl Drop for Pop {
+        fn drop(&mut self) {
+            LOCALS.with(|locals| locals.borrow_mut().truncate(self.0));
+        }
+    }
+    let _pop = Pop(LOCALS.with(|locals| {
+        let mut locals = locals.borrow_mut();
+        let len = locals.len();
+        locals.push(entry);
+        len
+    }));
+    f()
+}
+
+/// Future returned by [`LocalKey::scope`] and [`LocalKey::inherited_scope`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct TaskLocalFuture<F> {
+    entry: Entry,
+    future: F,
+}
+
+impl<F: Future> Future for TaskLocalFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        push(this.entry.clone(), || future.poll(cx))
+    }
+}
+
+/// Error returned by [`LocalKey::try_with`] when the value is not set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessError(());
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task-local value not set")
+    }
+}
+impl Error for AccessError {}
+
+/// Task-local values inherited by a spawned task.
+pub(crate) struct TaskLocals(Vec<Entry>);
+
+impl TaskLocals {
+    pub(crate) fn inherited() -> Self {
+        Self(LOCALS.with(|locals| {
+            locals
+                .borrow()
+                .iter()
+                .filter(|e| e.inherit)
+                .cloned()
+                .collect()
+        }))
+    }
+    pub(crate) fn enter<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        struct Restore<'a>(&'a mut Vec<Entry>);
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                LOCALS.with(|locals| swap(&mut *locals.borrow_mut(), self.0));
+            }
+        }
+        LOCALS.with(|locals| swap(&mut *locals.borrow_mut(), &mut self.0));
+        let _restore = Restore(&mut self.0);
+        f()
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    coop::poll_budget,
+    task::{JoinError, Priority, TaskId, TaskInfo},
+};
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    num::NonZeroUsize,
+    panic::{catch_unwind, AssertUnwindSafe, Location},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::Duration,
+};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    state: Mutex<PoolState>,
+    cv: Condvar,
+}
+
+struct PoolState {
+    jobs: VecDeque<Job>,
+    threads: usize,
+    idle_threads: usize,
+    max_threads: Option<usize>,
+}
+
+static POOL: Pool = Pool {
+    state: Mutex::new(PoolState {
+        jobs: VecDeque::new(),
+        threads: 0,
+        idle_threads: 0,
+        max_threads: None,
+    }),
+    cv: Condvar::new(),
+};
+
+impl Pool {
+    fn push(&'static self, job: Job) {
+        let mut s = self.state.lock().unwrap();
+        s.jobs.push_back(job);
+        if s.idle_threads >= s.jobs.len() {
+            self.cv.notify_one();
+            return;
+        }
+        let max_threads = s.max_threads.unwrap_or_else(default_max_threads);
+        if s.threads < max_threads {
+            // The lock is held while spawning, so that the job is still at the back of the queue on failure.
+            match thread::Builder::new()
+                .name("rt-local-blocking".into())
+                .spawn(move || self.worker())
+            {
+                Ok(_) => s.threads += 1,
+                Err(e) if s.threads == 0 => {
+                    let job = s.jobs.pop_back();
+                    drop(s);
+                    drop(job);
+                    panic!("failed to spawn thread: {e}");
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    fn worker(&self) {
+        let mut s = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = s.jobs.pop_front() {
+                drop(s);
+                job();
+                s = self.state.lock().unwrap();
+                continue;
+            }
+            s.idle_threads += 1;
+            let (guard, timeout) = self.cv.wait_timeout(s, KEEP_ALIVE).unwrap();
+            s = guard;
+            s.idle_threads -= 1;
+            if timeout.timed_out() && s.jobs.is_empty() {
+                s.threads -= 1;
+                return;
+            }
+        }
+    }
+}
+
+fn default_max_threads() -> usize {
+    thread::available_parallelism().map_or(4, NonZeroUsize::get)
+}
+
+/// Set the maximum number of threads used by [`spawn_blocking`].
+///
+/// The default is the number of available CPU cores.
+/// Threads are started on demand, and exit after being idle for a while.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+pub fn set_max_blocking_threads(n: usize) {
+    assert!(n > 0, "`n` must be non-zero");
+    POOL.state.lock().unwrap().max_threads = Some(n);
+}
+
+/// Run a blocking function on a built-in thread pool.
+///
+/// The returned [`BlockingTask`] completes with the return value of `f`, or with [`JoinError`] if `f` panicked.
+/// Dropping the [`BlockingTask`] does not stop `f`.
+///
+/// Use [`spawn_thread`] for long-running functions, so that they do not occupy the pool.
+///
+/// # Panics
+///
+/// Panics if no thread of the pool is running and a new thread cannot be created.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local_core::spawn_blocking;
+///
+/// # rt_local_core::runtime::blocking::run(async {
+/// let len = spawn_blocking(|| std::fs::read("Cargo.toml").map(|data| data.len()))
+///     .await
+///     .unwrap();
+/// # let _ = len;
+/// # });
+/// ```
+#[track_caller]
+pub fn spawn_blocking<F, T>(f: F) -> BlockingTask<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (task, job) = BlockingTask::new(f);
+    POOL.push(job);
+    task
+}
+
+/// Run a blocking function on a newly created thread.
+///
+/// The returned [`BlockingTask`] completes with the return value of `f`, or with [`JoinError`] if `f` panicked.
+/// Dropping the [`BlockingTask`] does not stop `f`.
+///
+/// # Panics
+///
+/// Panics if the thread cannot be created.
+#[track_caller]
+pub fn spawn_thread<F, T>(f: F) -> BlockingTask<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (task, job) = BlockingTask::new(f);
+    thread::Builder::new()
+        .name("rt-local-thread".into())
+        .spawn(job)
+        .expect("failed to spawn thread");
+    task
+}
+
+struct Shared<T> {
+    result: Option<Result<T, JoinError>>,
+    is_completed: bool,
+    waker: Option<Waker>,
+}
+
+/// A blocking function spawned by [`spawn_blocking`] or [`spawn_thread`].
+///
+/// Awaiting it returns the return value of the function, or [`JoinError`] if the function panicked.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct BlockingTask<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    info: TaskInfo,
+}
+
+impl<T: Send + 'static> BlockingTask<T> {
+    #[track_caller]
+    fn new(f: impl FnOnce() -> T + Send + 'static) -> (Self, Job) {
+        let info = TaskInfo::new(None, Location::caller(), Priority::Normal);
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            is_completed: false,
+            waker: None,
+        }));
+        let job = {
+            let shared = shared.clone();
+            let info = info.clone();
+            Box::new(move || {
+                let result =
+                    catch_unwind(AssertUnwindSafe(f)).map_err(|e| JoinError::panic(e, info));
+                let waker = {
+                    let mut s = shared.lock().unwrap();
+                    s.result = Some(result);
+                    s.is_completed = true;
+                    s.waker.take()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            })
+        };
+        (Self { shared, info }, job)
+    }
+}
+
+impl<T> BlockingTask<T> {
+    /// Returns `true` if the function has returned or panicked.
+    pub fn is_finished(&self) -> bool {
+        self.shared.lock().unwrap().is_completed
+    }
+
+    /// Returns the id of the task.
+    pub fn id(&self) -> TaskId {
+        self.info.id
+    }
+}
+impl<T> Future for BlockingTask<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        poll_budget(cx, |cx| {
+            let mut s = self.shared.lock().unwrap();
+            if let Some(result) = s.result.take() {
+                Poll::Ready(result)
+            } else {
+                s.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+    }
+}
+impl<T> fmt::Debug for BlockingTask<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingTask")
+            .field("id", &self.info.id)
+            .field("location", &self.info.location)
+            .field("is_finished", &self.is_finished())
+            .finish()
+    }
+}
@@ -0,0 +1,266 @@
+//! Utilities for tracking time.
+//!
+//! Timers are driven by the runtime backend, so no other asynchronous runtime is required to use them.
+use crate::{base_impl::Runtime, coop::poll_budget};
+use futures_core::Stream;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct TimerKey {
+    deadline: Instant,
+    seq: u64,
+}
+
+pub(crate) struct TimerQueue {
+    timers: BTreeMap<TimerKey, Waker>,
+    next_seq: u64,
+}
+
+impl TimerQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            timers: BTreeMap::new(),
+            next_seq: 0,
+        }
+    }
+    fn new_key(&mut self, deadline: Instant) -> TimerKey {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        TimerKey { deadline, seq }
+    }
+    fn set_waker(&mut self, key: TimerKey, waker: &Waker) {
+        match self.timers.get_mut(&key) {
+            Some(w) if w.will_wake(waker) => {}
+            Some(w) => *w = waker.clone(),
+            None => {
+                self.timers.insert(key, waker.clone());
+            }
+        }
+    }
+    fn remove(&mut self, key: TimerKey) {
+        self.timers.remove(&key);
+    }
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.timers.first_key_value().map(|(key, _)| key.deadline)
+    }
+    pub(crate) fn pop_expired(&mut self, now: Instant, wakers: &mut Vec<Waker>) {
+        while let Some(entry) = self.timers.first_entry() {
+            if entry.key().deadline > now {
+                break;
+            }
+            wakers.push(entry.remove());
+        }
+    }
+}
+
+/// Waits until `duration` has elapsed.
+///
+/// # Panics
+///
+/// Panics if the returned future is polled while the runtime is not running.
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(Instant::now() + duration)
+}
+
+/// Waits until `deadline` is reached.
+///
+/// # Panics
+///
+/// Panics if the returned future is polled while the runtime is not running.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        key: None,
+    }
+}
+
+/// Future returned by [`sleep`] and [`sleep_until`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    deadline: Instant,
+    key: Option<TimerKey>,
+}
+
+impl Sleep {
+    /// Returns the instant at which the future will complete.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns `true` if the deadline has been reached.
+    pub fn is_elapsed(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// Resets the future to complete at `deadline`.
+    pub fn reset(&mut self, deadline: Instant) {
+        self.unregister();
+        self.deadline = deadline;
+    }
+
+    fn poll_elapsed(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_elapsed() {
+            self.unregister();
+            return Poll::Ready(());
+        }
+        Runtime::with(|rt| {
+            let key = *self
+                .key
+                .get_or_insert_with(|| rt.timers.new_key(self.deadline));
+            rt.timers.set_waker(key, cx.waker());
+        });
+        Poll::Pending
+    }
+    fn unregister(&mut self) {
+        if let Some(key) = self.key.take() {
+            Runtime::try_with(|rt| rt.timers.remove(key));
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        poll_budget(cx, |cx| self.get_mut().poll_elapsed(cx))
+    }
+}
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+/// Requires a future to complete before `duration` has elapsed.
+///
+/// If the future completes in time, its output is returned in `Ok`, otherwise `Err(Elapsed)` is returned and the future is dropped.
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    timeout_at(Instant::now() + duration, future)
+}
+
+/// Requires a future to complete before `deadline` is reached.
+///
+/// If the future completes in time, its output is returned in `Ok`, otherwise `Err(Elapsed)` is returned and the future is dropped.
+pub fn timeout_at<F: Future>(deadline: Instant, future: F) -> Timeout<F> {
+    Timeout {
+        future,
+        sleep: sleep_until(deadline),
+    }
+}
+
+/// Future returned by [`timeout`] and [`timeout_at`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Timeout<F> {
+    future: F,
+    sleep: Sleep,
+}
+
+impl<F> Timeout<F> {
+    /// Consumes this timeout, returning the underlying future.
+    pub fn into_inner(self) -> F {
+        self.future
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        unsafe {
+            let this = self.get_unchecked_mut();
+            if let Poll::Ready(value) = Pin::new_unchecked(&mut this.future).poll(cx) {
+                return Poll::Ready(Ok(value));
+            }
+            Pin::new(&mut this.sleep).poll(cx).map(|_| Err(Elapsed(())))
+        }
+    }
+}
+
+/// Error returned by [`Timeout`] when the deadline has elapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed(());
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+impl Error for Elapsed {}
+
+/// Creates a new [`Interval`] that yields every `period`. The first tick completes immediately.
+///
+/// # Panics
+///
+/// Panics if `period` is zero.
+pub fn interval(period: Duration) -> Interval {
+    interval_at(Instant::now(), period)
+}
+
+/// Creates a new [`Interval`] that yields every `period`, with the first tick completing at `start`.
+///
+/// # Panics
+///
+/// Panics if `period` is zero.
+pub fn interval_at(start: Instant, period: Duration) -> Interval {
+    assert!(!period.is_zero(), "`period` must be non-zero");
+    Interval {
+        sleep: sleep_until(start),
+        period,
+    }
+}
+
+/// Periodic timer created by [`interval`] and [`interval_at`].
+///
+/// If a tick is missed, the next tick is scheduled one period after the time the missed tick was observed.
+pub struct Interval {
+    sleep: Sleep,
+    period: Duration,
+}
+
+impl Interval {
+    /// Waits until the next tick and returns the instant at which it was scheduled.
+    pub async fn tick(&mut self) -> Instant {
+        std::future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    /// Polls for the next tick.
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        if Pin::new(&mut self.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let deadline = self.sleep.deadline();
+        let now = Instant::now();
+        let mut next = deadline + self.period;
+        if next <= now {
+            next = now + self.period;
+        }
+        self.sleep.reset(next);
+        Poll::Ready(deadline)
+    }
+
+    /// Returns the period of the interval.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Resets the interval so that the next tick completes one period from now.
+    pub fn reset(&mut self) {
+        self.sleep.reset(Instant::now() + self.period);
+    }
+}
+
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_tick(cx).map(Some)
+    }
+}
@@ -0,0 +1,34 @@
+use std::{future::pending, sync::OnceLock, thread};
+use tokio::runtime::{Builder, EnterGuard, Handle};
+
+/// Tokio context entered while the runtime polls futures.
+pub(crate) struct TokioContext(Handle);
+
+impl TokioContext {
+    /// Use the tokio runtime of the current thread if there is one,
+    /// otherwise a shared current-thread runtime whose driver runs on a background thread.
+    pub(crate) fn new() -> Self {
+        Self(Handle::try_current().unwrap_or_else(|_| shared_handle()))
+    }
+    pub(crate) fn enter(&self) -> EnterGuard<'_> {
+        self.0.enter()
+    }
+}
+
+fn shared_handle() -> Handle {
+    static HANDLE: OnceLock<Handle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            let rt = Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build tokio runtime");
+            let handle = rt.handle().clone();
+            thread::Builder::new()
+                .name("rt-local-tokio".into())
+                .spawn(move || rt.block_on(pending::<()>()))
+                .expect("failed to spawn thread");
+            handle
+        })
+        .clone()
+}
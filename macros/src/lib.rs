@@ -29,3 +29,45 @@ pub fn windows_test(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn windows_main(attr: TokenStream, item: TokenStream) -> TokenStream {
     resolve_attr(attr, item, |attr, item| build(attr, item, "windows", false))
 }
+
+#[proc_macro_attribute]
+pub fn async_io_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "async_io", true))
+}
+
+#[proc_macro_attribute]
+pub fn async_io_main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| {
+        build(attr, item, "async_io", false)
+    })
+}
+
+#[proc_macro_attribute]
+pub fn glib_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "glib", true))
+}
+
+#[proc_macro_attribute]
+pub fn glib_main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "glib", false))
+}
+
+#[proc_macro_attribute]
+pub fn calloop_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "calloop", true))
+}
+
+#[proc_macro_attribute]
+pub fn calloop_main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "calloop", false))
+}
+
+#[proc_macro_attribute]
+pub fn slint_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "slint", true))
+}
+
+#[proc_macro_attribute]
+pub fn slint_main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    resolve_attr(attr, item, |attr, item| build(attr, item, "slint", false))
+}
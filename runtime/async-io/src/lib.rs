@@ -0,0 +1,83 @@
+use async_io::{block_on, Timer};
+use rt_local_core::base::{idle, EventLoop};
+use std::{
+    cell::Cell,
+    future::{poll_fn, Future},
+    marker::PhantomData,
+    ops::ControlFlow,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Poll, Wake},
+    time::Instant,
+};
+
+/// Executes the specified future and blocks until it completes.
+///
+/// While waiting, the current thread drives the [async-io](https://crates.io/crates/async-io) reactor,
+/// so I/O types such as [`async_io::Async`] and [`async_io::Timer`] can be used in tasks.
+pub fn run<T>(future: impl Future<Output = T>) -> T {
+    rt_local_core::base::run(&AsyncIoEventLoop::new(), future)
+}
+
+struct AsyncIoEventLoop {
+    waker: Arc<Waker>,
+    deadline: Cell<Option<Instant>>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl AsyncIoEventLoop {
+    fn new() -> Self {
+        Self {
+            waker: Arc::new(Waker {
+                reactor: Mutex::new(None),
+            }),
+            deadline: Cell::new(None),
+            _not_send: PhantomData,
+        }
+    }
+}
+impl EventLoop for AsyncIoEventLoop {
+    fn waker(&self) -> std::task::Waker {
+        self.waker.clone().into()
+    }
+    fn run<T>(&self, mut poll: impl FnMut() -> ControlFlow<T>) -> T {
+        let mut timer = Timer::never();
+        block_on(poll_fn(|cx| {
+            *self.waker.reactor.lock().unwrap() = Some(cx.waker().clone());
+            loop {
+                if let ControlFlow::Break(value) = poll() {
+                    return Poll::Ready(value);
+                }
+                if !idle() {
+                    break;
+                }
+            }
+            if let Some(deadline) = self.deadline.get() {
+                timer.set_at(deadline);
+                if Pin::new(&mut timer).poll(cx).is_ready() {
+                    cx.waker().wake_by_ref();
+                }
+            }
+            Poll::Pending
+        }))
+    }
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+    }
+}
+
+/// Unparks the thread running [`run`], which may be blocked in the reactor.
+struct Waker {
+    reactor: Mutex<Option<std::task::Waker>>,
+}
+impl Wake for Waker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        let waker = self.reactor.lock().unwrap().clone();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
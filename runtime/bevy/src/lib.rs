@@ -0,0 +1,122 @@
+use bevy_app::{App, Last, Plugin, PreUpdate};
+use bevy_ecs::system::NonSend;
+use rt_local_core::{spawn_local, Task};
+use std::{fmt, future::Future, marker::PhantomData};
+#[cfg(not(feature = "winit"))]
+use std::{
+    sync::Arc,
+    task::{Wake, Waker},
+};
+
+#[cfg(feature = "winit")]
+mod winit;
+
+/// Plugin that runs the rt-local runtime on the main thread of a Bevy [`App`].
+///
+/// The runtime is entered when the plugin is built, and [`RtLocalRuntime`] is inserted as a non-send resource.
+/// Tasks are polled in [`PreUpdate`] each frame,
+/// and tasks waiting for [`wait_for_idle`](rt_local_core::wait_for_idle) are resumed in [`Last`].
+///
+/// Since tasks are polled every frame, waking a task takes effect in the next frame at the latest.
+///
+/// By default, nothing triggers a frame when a task is woken or when a timer of [`time`](rt_local_core::time) expires,
+/// so only apps that are updated continuously, such as with `UpdateMode::Continuous` of `WinitSettings`, are supported.
+/// With the `winit` feature, these events send `WakeUp` through the `EventLoopProxy` of `WinitPlugin`,
+/// so tasks also progress with reactive update modes.
+/// The `winit` feature does not select a windowing backend, so on Linux the app must enable the `x11` or `wayland` feature of `bevy`.
+///
+/// # Examples
+///
+/// ```
+/// use bevy_app::{App, Startup};
+/// use bevy_ecs::system::NonSend;
+/// use rt_local_runtime_bevy::{RtLocalPlugin, RtLocalRuntime};
+///
+/// fn setup(rt: NonSend<RtLocalRuntime>) {
+///     rt.spawn_local(async {
+///         // ...
+///     })
+///     .detach();
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugins(RtLocalPlugin).add_systems(Startup, setup);
+/// app.update();
+/// ```
+#[derive(Debug, Default)]
+pub struct RtLocalPlugin;
+
+impl Plugin for RtLocalPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_non_send_resource(RtLocalRuntime::new())
+            .add_systems(PreUpdate, poll)
+            .add_systems(Last, idle);
+        #[cfg(feature = "winit")]
+        winit::build(app, poll, idle);
+    }
+}
+
+fn poll(_rt: NonSend<RtLocalRuntime>) {
+    rt_local_core::base::poll();
+}
+fn idle(_rt: NonSend<RtLocalRuntime>) {
+    rt_local_core::base::idle();
+}
+
+/// Non-send resource inserted by [`RtLocalPlugin`].
+///
+/// Systems that take `NonSend<RtLocalRuntime>` run on the main thread, so they can spawn tasks with [`RtLocalRuntime::spawn_local`].
+/// The runtime is stopped when the resource is dropped along with the [`App`].
+pub struct RtLocalRuntime {
+    #[cfg(feature = "winit")]
+    wake: std::sync::Arc<winit::WinitWake>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl RtLocalRuntime {
+    #[cfg(not(feature = "winit"))]
+    fn new() -> Self {
+        rt_local_core::base::enter(Waker::from(Arc::new(NoopWake)));
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+    #[cfg(feature = "winit")]
+    fn new() -> Self {
+        let wake = std::sync::Arc::new(winit::WinitWake::default());
+        rt_local_core::base::enter(wake.clone().into());
+        Self {
+            wake,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Spawn a future on the main thread.
+    ///
+    /// See [`spawn_local`](rt_local_core::spawn_local) for details.
+    #[must_use]
+    #[track_caller]
+    pub fn spawn_local<F: Future + 'static>(&self, future: F) -> Task<F::Output> {
+        spawn_local(future)
+    }
+}
+impl Drop for RtLocalRuntime {
+    fn drop(&mut self) {
+        #[cfg(feature = "winit")]
+        self.wake.close();
+        rt_local_core::base::leave();
+    }
+}
+#[cfg(not(feature = "winit"))]
+struct NoopWake;
+
+#[cfg(not(feature = "winit"))]
+impl Wake for NoopWake {
+    fn wake(self: Arc<Self>) {}
+}
+
+impl fmt::Debug for RtLocalRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RtLocalRuntime").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::RtLocalRuntime;
+use bevy_app::{App, Last, PreUpdate};
+use bevy_ecs::{
+    schedule::{IntoSystemConfigs, IntoSystemSet},
+    system::NonSend,
+};
+use bevy_winit::{EventLoopProxy, WakeUp};
+use rt_local_core::base::next_deadline;
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    task::Wake,
+    thread,
+    time::Instant,
+};
+
+/// Sends [`WakeUp`] to the winit event loop, so that the app is updated even with reactive `WinitSettings`.
+///
+/// The proxy is available only after the winit runner has started, so wakes before that are ignored.
+/// Timer deadlines are waited for on a helper thread.
+#[derive(Default)]
+pub(crate) struct WinitWake {
+    proxy: Mutex<Option<EventLoopProxy<WakeUp>>>,
+    timer: Mutex<TimerState>,
+    cv: Condvar,
+}
+
+#[derive(Default)]
+struct TimerState {
+    deadline: Option<Instant>,
+    is_running: bool,
+    is_closed: bool,
+}
+
+impl WinitWake {
+    fn send(&self) {
+        if let Some(proxy) = &*self.proxy.lock().unwrap() {
+            let _ = proxy.send_event(WakeUp);
+        }
+    }
+    fn set_deadline(self: &Arc<Self>, deadline: Option<Instant>) {
+        let mut s = self.timer.lock().unwrap();
+        if s.deadline == deadline {
+            return;
+        }
+        s.deadline = deadline;
+        if deadline.is_some() && !s.is_running {
+            s.is_running = true;
+            let this = self.clone();
+            thread::Builder::new()
+                .name("rt-local-bevy-timer".into())
+                .spawn(move || this.run_timer())
+                .expect("failed to spawn thread");
+        }
+        self.cv.notify_one();
+    }
+    fn run_timer(&self) {
+        let mut s = self.timer.lock().unwrap();
+        while !s.is_closed {
+            match s.deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        s.deadline = None;
+                        drop(s);
+                        self.send();
+                        s = self.timer.lock().unwrap();
+                    } else {
+                        s = self.cv.wait_timeout(s, deadline - now).unwrap().0;
+                    }
+                }
+                None => s = self.cv.wait(s).unwrap(),
+            }
+        }
+    }
+    pub(crate) fn close(&self) {
+        self.timer.lock().unwrap().is_closed = true;
+        self.cv.notify_one();
+    }
+}
+impl Wake for WinitWake {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.send();
+    }
+}
+
+pub(crate) fn build<M0, M1>(
+    app: &mut App,
+    poll: impl IntoSystemSet<M0>,
+    idle: impl IntoSystemSet<M1>,
+) {
+    app.add_systems(PreUpdate, set_proxy.before(poll))
+        .add_systems(Last, set_deadline.after(idle));
+}
+
+fn set_proxy(rt: NonSend<RtLocalRuntime>, proxy: Option<NonSend<EventLoopProxy<WakeUp>>>) {
+    if let Some(proxy) = proxy {
+        let mut p = rt.wake.proxy.lock().unwrap();
+        if p.is_none() {
+            *p = Some((*proxy).clone());
+        }
+    }
+}
+fn set_deadline(rt: NonSend<RtLocalRuntime>) {
+    rt.wake.set_deadline(next_deadline());
+}
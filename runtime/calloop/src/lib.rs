@@ -0,0 +1,196 @@
+use calloop::{
+    ping::{make_ping, Ping},
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use rt_local_core::base::{idle, next_deadline, EventLoop};
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    ops::ControlFlow,
+    rc::Rc,
+    sync::Arc,
+    task::Wake,
+    time::Instant,
+};
+
+/// Executes the specified future and blocks until it completes.
+///
+/// The future is run on a newly created [`calloop::EventLoop`].
+/// To run tasks on an event loop owned by the application, use [`RtLocalRuntime`].
+///
+/// # Panics
+///
+/// Panics if the event loop cannot be created or fails to dispatch events.
+pub fn run<T>(future: impl Future<Output = T>) -> T {
+    rt_local_core::base::run(&CalloopEventLoop::new(), future)
+}
+
+struct CalloopEventLoop {
+    event_loop: RefCell<calloop::EventLoop<'static, ()>>,
+    ping: Ping,
+    deadline: Cell<Option<Instant>>,
+}
+
+impl CalloopEventLoop {
+    fn new() -> Self {
+        let event_loop = calloop::EventLoop::try_new().expect("failed to create event loop");
+        let (ping, source) = make_ping().expect("failed to create ping source");
+        event_loop
+            .handle()
+            .insert_source(source, |_, _, _| {})
+            .expect("failed to insert ping source");
+        Self {
+            event_loop: RefCell::new(event_loop),
+            ping,
+            deadline: Cell::new(None),
+        }
+    }
+}
+impl EventLoop for CalloopEventLoop {
+    fn waker(&self) -> std::task::Waker {
+        Arc::new(Waker(self.ping.clone())).into()
+    }
+    fn run<T>(&self, mut poll: impl FnMut() -> ControlFlow<T>) -> T {
+        loop {
+            if let ControlFlow::Break(value) = poll() {
+                return value;
+            }
+            if idle() {
+                continue;
+            }
+            let timeout = self
+                .deadline
+                .get()
+                .map(|d| d.saturating_duration_since(Instant::now()));
+            self.event_loop
+                .borrow_mut()
+                .dispatch(timeout, &mut ())
+                .expect("failed to dispatch events");
+        }
+    }
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+    }
+}
+
+struct Waker(Ping);
+
+impl Wake for Waker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.ping();
+    }
+}
+
+/// Asynchronous runtime attached to a [`calloop::EventLoop`] owned by the application.
+///
+/// A ping source is inserted as the runtime waker, and tasks are polled from its callback.
+/// Tasks waiting for [`wait_for_idle`](rt_local_core::wait_for_idle) are resumed from an idle callback.
+/// The sources are removed and the runtime is stopped when `RtLocalRuntime` is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use calloop::EventLoop;
+/// use rt_local_core::spawn_local;
+/// use rt_local_runtime_calloop::RtLocalRuntime;
+///
+/// let mut event_loop = EventLoop::<bool>::try_new().unwrap();
+/// let _rt = RtLocalRuntime::new(&event_loop.handle()).unwrap();
+/// let signal = event_loop.get_signal();
+/// spawn_local(async move { signal.stop() }).detach();
+/// event_loop.run(None, &mut false, |_| {}).unwrap();
+/// ```
+pub struct RtLocalRuntime<'l, Data> {
+    state: Rc<State<'l, Data>>,
+}
+
+struct State<'l, Data> {
+    handle: LoopHandle<'l, Data>,
+    ping: Ping,
+    ping_token: Cell<Option<RegistrationToken>>,
+    timer: Cell<Option<(Instant, RegistrationToken)>>,
+    is_idle_scheduled: Cell<bool>,
+}
+
+impl<'l, Data: 'l> RtLocalRuntime<'l, Data> {
+    /// Start the runtime on the current thread, driven by the event loop of `handle`.
+    ///
+    /// The event loop must be dispatched by the current thread.
+    pub fn new(handle: &LoopHandle<'l, Data>) -> calloop::Result<Self> {
+        let (ping, source) = make_ping()?;
+        let state = Rc::new(State {
+            handle: handle.clone(),
+            ping: ping.clone(),
+            ping_token: Cell::new(None),
+            timer: Cell::new(None),
+            is_idle_scheduled: Cell::new(false),
+        });
+        let token = handle.insert_source(source, {
+            let state = Rc::downgrade(&state);
+            move |_, _, _| {
+                if let Some(state) = state.upgrade() {
+                    state.poll();
+                }
+            }
+        })?;
+        state.ping_token.set(Some(token));
+        rt_local_core::base::enter(Arc::new(Waker(ping.clone())).into());
+        ping.ping();
+        Ok(Self { state })
+    }
+}
+impl<Data> Drop for RtLocalRuntime<'_, Data> {
+    fn drop(&mut self) {
+        if let Some(token) = self.state.ping_token.take() {
+            self.state.handle.remove(token);
+        }
+        if let Some((_, token)) = self.state.timer.take() {
+            self.state.handle.remove(token);
+        }
+        rt_local_core::base::leave();
+    }
+}
+
+impl<'l, Data: 'l> State<'l, Data> {
+    fn poll(self: &Rc<Self>) {
+        rt_local_core::base::poll();
+        if !self.is_idle_scheduled.replace(true) {
+            let state = Rc::downgrade(self);
+            self.handle.insert_idle(move |_| {
+                if let Some(state) = state.upgrade() {
+                    state.is_idle_scheduled.set(false);
+                    if idle() {
+                        state.ping.ping();
+                    }
+                }
+            });
+        }
+        let deadline = next_deadline();
+        let timer = self.timer.take();
+        if let Some((timer_deadline, token)) = timer {
+            if Some(timer_deadline) == deadline {
+                self.timer.set(timer);
+                return;
+            }
+            self.handle.remove(token);
+        }
+        if let Some(deadline) = deadline {
+            let state = Rc::downgrade(self);
+            let token = self
+                .handle
+                .insert_source(Timer::from_deadline(deadline), move |_, _, _| {
+                    if let Some(state) = state.upgrade() {
+                        state.timer.set(None);
+                        state.ping.ping();
+                    }
+                    TimeoutAction::Drop
+                })
+                .expect("failed to insert timer source");
+            self.timer.set(Some((deadline, token)));
+        }
+    }
+}
@@ -1,14 +1,170 @@
-use std::{marker::PhantomData, sync::Arc, task::Wake};
+use std::{future::Future, time::Duration};
 
-use eframe::{run_native, App, CreationContext, Frame, NativeOptions, Result};
-use egui::Context;
+use eframe::{glow, App, CreationContext, Frame, NativeOptions, Result, Storage};
+use egui::{Context, RawInput, Visuals};
+use rt_local_core::{spawn_local, Task};
+
+pub use rt_local_runtime_egui::{
+    after_frame, before_frame, next_frame, spawn_for_viewport, ExitGuard, FrameFuture, FrameTime,
+};
+
+/// Run an [`eframe::App`] with the asynchronous runtime, like [`eframe::run_native`].
+///
+/// The runtime is started before `app_creator` is called, so the app can spawn tasks while being created.
+/// All methods of [`App`] are forwarded to the app.
+pub fn run_native<A: App + 'static>(
+    app_name: &str,
+    native_options: NativeOptions,
+    app_creator: impl FnOnce(&CreationContext) -> A + 'static,
+) -> Result<()> {
+    eframe::run_native(
+        app_name,
+        native_options,
+        Box::new(|cc| {
+            let rt = RtLocalRuntime::new(cc);
+            let app = app_creator(cc);
+            Ok(Box::new(RtLocalApp::new(app, rt, None)))
+        }),
+    )
+}
+
+/// Run an [`AsyncApp`] with the asynchronous runtime.
+///
+/// In addition to [`run_native`], [`AsyncApp::setup`] is spawned after the app is created,
+/// and [`AsyncApp::on_exit`] is run to completion before the window closes.
+pub fn run_async_native<A: AsyncApp + 'static>(
+    app_name: &str,
+    native_options: NativeOptions,
+    app_creator: impl FnOnce(&CreationContext) -> A + 'static,
+) -> Result<()> {
+    eframe::run_native(
+        app_name,
+        native_options,
+        Box::new(|cc| {
+            let rt = RtLocalRuntime::new(cc);
+            let mut app = app_creator(cc);
+            spawn_local(app.setup()).detach();
+            Ok(Box::new(RtLocalApp::new(app, rt, Some(spawn_exit::<A>))))
+        }),
+    )
+}
+
+/// An [`eframe::App`] with asynchronous setup and exit.
+///
+/// Use [`run_async_native`] to run it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rt_local_runtime_eframe::{run_async_native, AsyncApp};
+/// use std::future::Future;
+///
+/// struct MyApp;
+///
+/// impl eframe::App for MyApp {
+///     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+///         egui::CentralPanel::default().show(ctx, |ui| ui.label("hello"));
+///     }
+/// }
+/// impl AsyncApp for MyApp {
+///     fn setup(&mut self) -> impl Future<Output = ()> + 'static {
+///         async {
+///             // load data ...
+///         }
+///     }
+///     fn on_exit(&mut self) -> impl Future<Output = ()> + 'static {
+///         async {
+///             // save data ...
+///         }
+///     }
+/// }
+///
+/// fn main() -> eframe::Result<()> {
+///     run_async_native("my app", Default::default(), |_cc| MyApp)
+/// }
+/// ```
+pub trait AsyncApp: App {
+    /// Returns a future that is spawned as a task after the app is created.
+    ///
+    /// The returned future cannot borrow the app.
+    /// Use shared state such as `Rc<RefCell<_>>` to update the app from the future.
+    fn setup(&mut self) -> impl Future<Output = ()> + 'static {
+        async {}
+    }
+
+    /// Returns a future that is run when closing the root viewport is requested.
+    ///
+    /// The close is canceled until the future completes, and then the window closes.
+    /// [`App::on_exit`] is called after that.
+    fn on_exit(&mut self) -> impl Future<Output = ()> + 'static {
+        async {}
+    }
+}
+
+fn spawn_exit<A: AsyncApp>(app: &mut A) -> Task<()> {
+    spawn_local(AsyncApp::on_exit(app))
+}
+
+struct RtLocalApp<A> {
+    app: A,
+    exit: Option<fn(&mut A) -> Task<()>>,
+    exit_guard: ExitGuard,
+    rt: RtLocalRuntime,
+}
+
+impl<A: App> RtLocalApp<A> {
+    fn new(app: A, rt: RtLocalRuntime, exit: Option<fn(&mut A) -> Task<()>>) -> Self {
+        Self {
+            app,
+            exit,
+            exit_guard: ExitGuard::new(),
+            rt,
+        }
+    }
+    fn update_exit(&mut self, ctx: &Context) {
+        if let Some(exit) = self.exit {
+            self.exit_guard.update(ctx, || exit(&mut self.app));
+        }
+    }
+}
+
+impl<A: App> App for RtLocalApp<A> {
+    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
+        self.rt.before_update();
+        self.app.update(ctx, frame);
+        self.update_exit(ctx);
+        self.rt.after_update();
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        self.app.as_any_mut()
+    }
+    fn save(&mut self, storage: &mut dyn Storage) {
+        self.app.save(storage);
+    }
+    fn on_exit(&mut self, gl: Option<&glow::Context>) {
+        self.app.on_exit(gl);
+    }
+    fn auto_save_interval(&self) -> Duration {
+        self.app.auto_save_interval()
+    }
+    fn clear_color(&self, visuals: &Visuals) -> [f32; 4] {
+        self.app.clear_color(visuals)
+    }
+    fn persist_egui_memory(&self) -> bool {
+        self.app.persist_egui_memory()
+    }
+    fn raw_input_hook(&mut self, ctx: &Context, raw_input: &mut RawInput) {
+        self.app.raw_input_hook(ctx, raw_input);
+    }
+}
 
 pub fn run_simple_native(
     app_name: &str,
     native_options: NativeOptions,
     update_fun: impl FnMut(&Context, &mut Frame) + 'static,
 ) -> Result<()> {
-    run_native(
+    eframe::run_native(
         app_name,
         native_options,
         Box::new(|ctx| {
@@ -37,44 +193,19 @@ impl Drop for SimpleApp {
     fn drop(&mut self) {}
 }
 
-#[derive(Default)]
-struct PhantomNotSend(PhantomData<*mut ()>);
-
-pub struct RtLocalRuntime {
-    ctx: Context,
-    _not_send: PhantomNotSend,
-}
+/// Asynchronous runtime for [`eframe::App`].
+///
+/// This is a wrapper of [`rt_local_runtime_egui::RtLocalRuntime`] created from [`CreationContext`].
+pub struct RtLocalRuntime(rt_local_runtime_egui::RtLocalRuntime);
 
 impl RtLocalRuntime {
     pub fn new(ctx: &CreationContext) -> Self {
-        rt_local_core::base::enter(Arc::new(EguiWake(ctx.egui_ctx.clone())).into());
-        Self {
-            ctx: ctx.egui_ctx.clone(),
-            _not_send: PhantomNotSend::default(),
-        }
+        Self(rt_local_runtime_egui::RtLocalRuntime::new(&ctx.egui_ctx))
     }
     pub fn before_update(&self) {
-        rt_local_core::base::poll();
+        self.0.before_update();
     }
     pub fn after_update(&self) {
-        if self.ctx.has_requested_repaint() {
-            rt_local_core::base::idle();
-        }
-    }
-}
-impl Drop for RtLocalRuntime {
-    fn drop(&mut self) {
-        rt_local_core::base::leave();
-    }
-}
-
-struct EguiWake(Context);
-
-impl Wake for EguiWake {
-    fn wake(self: Arc<Self>) {
-        self.wake_by_ref();
-    }
-    fn wake_by_ref(self: &Arc<Self>) {
-        self.0.request_repaint();
+        self.0.after_update();
     }
 }
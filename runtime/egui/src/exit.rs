@@ -0,0 +1,81 @@
+use egui::{Context, ViewportCommand};
+use rt_local_core::Task;
+
+#[derive(Default)]
+enum ExitState {
+    #[default]
+    Running,
+    Exiting(Task<()>),
+    Exited,
+}
+
+/// Delays closing the root viewport until an exit task completes.
+///
+/// Call [`ExitGuard::update`] once per frame of the root viewport.
+/// When closing is requested, the close is canceled with [`ViewportCommand::CancelClose`] and the exit task is spawned.
+/// Further close requests are canceled while the task is running.
+/// Once the task has finished, including by panicking, [`ViewportCommand::Close`] is sent and the viewport closes.
+///
+/// # Examples
+///
+/// ```
+/// use egui::{Context, RawInput};
+/// use rt_local_core::spawn_local;
+/// use rt_local_runtime_egui::{ExitGuard, RtLocalRuntime};
+///
+/// let ctx = Context::default();
+/// let rt = RtLocalRuntime::new(&ctx);
+/// let mut exit = ExitGuard::new();
+/// rt.before_update();
+/// let _ = ctx.run(RawInput::default(), |ctx| {
+///     exit.update(ctx, || {
+///         spawn_local(async {
+///             // save data ...
+///         })
+///     });
+/// });
+/// rt.after_update();
+/// ```
+#[derive(Default)]
+pub struct ExitGuard {
+    state: ExitState,
+}
+
+impl ExitGuard {
+    /// Create a guard that has not started exiting.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle the close request of the current frame, spawning the exit task with `spawn_exit` on the first request.
+    ///
+    /// Call in the frame of the root viewport, before [`RtLocalRuntime::after_update`](crate::RtLocalRuntime::after_update).
+    pub fn update(&mut self, ctx: &Context, spawn_exit: impl FnOnce() -> Task<()>) {
+        if let ExitState::Exiting(task) = &self.state {
+            if task.is_finished() {
+                self.state = ExitState::Exited;
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+            }
+        }
+        if ctx.input(|i| i.viewport().close_requested()) {
+            match self.state {
+                ExitState::Running => {
+                    self.state = ExitState::Exiting(spawn_exit());
+                    ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+                }
+                ExitState::Exiting(_) => ctx.send_viewport_cmd(ViewportCommand::CancelClose),
+                ExitState::Exited => {}
+            }
+        }
+    }
+
+    /// Returns `true` if the exit task is running.
+    pub fn is_exiting(&self) -> bool {
+        matches!(self.state, ExitState::Exiting(_))
+    }
+
+    /// Returns `true` if the exit task has finished and the viewport has been asked to close.
+    pub fn is_exited(&self) -> bool {
+        matches!(self.state, ExitState::Exited)
+    }
+}
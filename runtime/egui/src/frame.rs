@@ -0,0 +1,204 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+/// Timing of a frame, returned by the futures created by [`next_frame`], [`before_frame`] and [`after_frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTime {
+    /// The instant at which the frame started.
+    pub time: Instant,
+    /// The time elapsed since the previous frame started.
+    pub delta: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Before = 0,
+    After = 1,
+}
+
+struct FrameState {
+    time: FrameTime,
+    counts: [u64; 2],
+    waiters: [BTreeMap<u64, Waker>; 2],
+    next_key: u64,
+}
+
+thread_local! {
+    static FRAME: RefCell<Option<FrameState>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn enter() {
+    FRAME.with(|f| {
+        *f.borrow_mut() = Some(FrameState {
+            time: FrameTime {
+                time: Instant::now(),
+                delta: Duration::ZERO,
+            },
+            counts: [0; 2],
+            waiters: Default::default(),
+            next_key: 0,
+        })
+    });
+}
+pub(crate) fn leave() {
+    let state = FRAME.with(|f| f.borrow_mut().take());
+    drop(state);
+}
+
+/// Start a frame and wake the futures waiting for it. Returns `true` if any future was woken.
+pub(crate) fn begin() -> bool {
+    with_state(|s| {
+        let now = Instant::now();
+        s.time = FrameTime {
+            time: now,
+            delta: now.saturating_duration_since(s.time.time),
+        };
+    });
+    fire(Phase::Before)
+}
+
+/// End a frame and wake the futures waiting for it. Returns `true` if any future was woken.
+pub(crate) fn end() -> bool {
+    fire(Phase::After)
+}
+
+/// Returns `true` if any future is waiting for a frame.
+pub(crate) fn is_waiting() -> bool {
+    FRAME.with(|f| {
+        f.borrow()
+            .as_ref()
+            .is_some_and(|s| s.waiters.iter().any(|w| !w.is_empty()))
+    })
+}
+
+fn fire(phase: Phase) -> bool {
+    let waiters = with_state(|s| {
+        s.counts[phase as usize] += 1;
+        std::mem::take(&mut s.waiters[phase as usize])
+    });
+    let is_woken = !waiters.is_empty();
+    for waker in waiters.into_values() {
+        waker.wake();
+    }
+    is_woken
+}
+
+#[track_caller]
+fn with_state<T>(f: impl FnOnce(&mut FrameState) -> T) -> T {
+    FRAME.with(|s| {
+        f(s.borrow_mut()
+            .as_mut()
+            .expect("`RtLocalRuntime` is not running on the current thread"))
+    })
+}
+
+/// Wait for the start of a frame that begins after the returned future is first polled, and return its timing.
+///
+/// Useful for animations: loop over `next_frame().await` and advance by [`FrameTime::delta`].
+/// Unlike [`before_frame`], which waits for the first frame that begins after it is called,
+/// a future created before a frame starts and awaited during that frame waits for the following frame.
+/// A repaint is requested while the future is pending.
+///
+/// # Panics
+///
+/// Panics if [`RtLocalRuntime`](crate::RtLocalRuntime) is not running on the current thread.
+#[track_caller]
+pub fn next_frame() -> FrameFuture {
+    with_state(|_| {});
+    FrameFuture {
+        phase: Phase::Before,
+        count: None,
+        key: None,
+    }
+}
+
+/// Wait until [`RtLocalRuntime::before_update`](crate::RtLocalRuntime::before_update) of the first frame that begins after this call.
+///
+/// The task is resumed before the app updates the UI of that frame.
+/// If such a frame has already started when the future is polled, the future is ready immediately.
+/// A repaint is requested while the future is pending.
+///
+/// # Panics
+///
+/// Panics if [`RtLocalRuntime`](crate::RtLocalRuntime) is not running on the current thread.
+#[track_caller]
+pub fn before_frame() -> FrameFuture {
+    FrameFuture::new(Phase::Before)
+}
+
+/// Wait until [`RtLocalRuntime::after_update`](crate::RtLocalRuntime::after_update) of the current frame,
+/// or of the next frame if called from a task resumed by `after_update`.
+///
+/// The task is resumed after the app has updated the UI of that frame, so the layout of the frame is available.
+/// A repaint is requested while the future is pending.
+///
+/// # Panics
+///
+/// Panics if [`RtLocalRuntime`](crate::RtLocalRuntime) is not running on the current thread.
+#[track_caller]
+pub fn after_frame() -> FrameFuture {
+    FrameFuture::new(Phase::After)
+}
+
+/// Future returned by [`next_frame`], [`before_frame`] and [`after_frame`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct FrameFuture {
+    phase: Phase,
+    count: Option<u64>,
+    key: Option<u64>,
+}
+
+impl FrameFuture {
+    #[track_caller]
+    fn new(phase: Phase) -> Self {
+        let count = with_state(|s| s.counts[phase as usize]);
+        Self {
+            phase,
+            count: Some(count),
+            key: None,
+        }
+    }
+}
+impl Future for FrameFuture {
+    type Output = FrameTime;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let phase = this.phase as usize;
+        let key = &mut this.key;
+        let count = &mut this.count;
+        with_state(|s| {
+            let count = *count.get_or_insert(s.counts[phase]);
+            if s.counts[phase] > count {
+                if let Some(key) = key.take() {
+                    s.waiters[phase].remove(&key);
+                }
+                return Poll::Ready(s.time);
+            }
+            let key = *key.get_or_insert_with(|| {
+                s.next_key += 1;
+                s.next_key
+            });
+            s.waiters[phase].insert(key, cx.waker().clone());
+            Poll::Pending
+        })
+    }
+}
+impl Drop for FrameFuture {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            let phase = self.phase as usize;
+            FRAME.with(|f| {
+                if let Some(s) = f.borrow_mut().as_mut() {
+                    s.waiters[phase].remove(&key);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Wake,
+    time::Instant,
+};
+
+use egui::{Context, ViewportId};
+
+mod exit;
+mod frame;
+mod viewport;
+
+pub use exit::ExitGuard;
+pub use frame::{after_frame, before_frame, next_frame, FrameFuture, FrameTime};
+pub use viewport::spawn_for_viewport;
+
+#[derive(Default)]
+struct PhantomNotSend(PhantomData<*mut ()>);
+
+/// Asynchronous runtime driven by an [`egui::Context`].
+///
+/// Call [`RtLocalRuntime::before_update`] and [`RtLocalRuntime::after_update`] around each frame of the root viewport,
+/// such as around [`Context::run`] or `eframe::App::update`.
+/// Waking a task requests a repaint of the root viewport, where tasks are polled, and timers request a repaint when they expire.
+/// Woken tasks are also polled at the start of the frames of other viewports, which is where tasks spawned by [`spawn_for_viewport`] request a repaint.
+/// Tasks can wait for a phase of a frame with [`next_frame`], [`before_frame`] and [`after_frame`].
+///
+/// The runtime is stopped when `RtLocalRuntime` is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use egui::{Context, RawInput};
+/// use rt_local_core::spawn_local;
+/// use rt_local_runtime_egui::RtLocalRuntime;
+///
+/// let ctx = Context::default();
+/// let rt = RtLocalRuntime::new(&ctx);
+/// spawn_local(async {
+///     // ...
+/// })
+/// .detach();
+/// rt.before_update();
+/// let _ = ctx.run(RawInput::default(), |ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| ui.label("hello"));
+/// });
+/// rt.after_update();
+/// ```
+pub struct RtLocalRuntime {
+    ctx: Context,
+    is_running: Arc<AtomicBool>,
+    _not_send: PhantomNotSend,
+}
+
+impl RtLocalRuntime {
+    /// Start the runtime on the current thread, driven by `ctx`.
+    pub fn new(ctx: &Context) -> Self {
+        rt_local_core::base::enter(Arc::new(EguiWake(ctx.clone())).into());
+        frame::enter();
+        viewport::enter(ctx);
+        // egui cannot unregister the callback, so it does nothing once this runtime is dropped.
+        let is_running = Arc::new(AtomicBool::new(true));
+        ctx.on_begin_frame(
+            "rt_local",
+            Arc::new({
+                let is_running = is_running.clone();
+                move |ctx| {
+                    if is_running.load(Ordering::Relaxed) {
+                        viewport::begin_frame(ctx);
+                    }
+                }
+            }),
+        );
+        Self {
+            ctx: ctx.clone(),
+            is_running,
+            _not_send: PhantomNotSend::default(),
+        }
+    }
+
+    /// Returns the context driving the runtime.
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// Resume the futures waiting for the frame and poll the woken tasks. Call at the start of each frame.
+    pub fn before_update(&self) {
+        frame::begin();
+        rt_local_core::base::poll();
+    }
+
+    /// Resume the futures waiting for the end of the frame and idle tasks, cancel the tasks of closed viewports,
+    /// and schedule a repaint for the next timer. Call at the end of each frame.
+    pub fn after_update(&self) {
+        viewport::update(&self.ctx);
+        if frame::end() {
+            rt_local_core::base::poll();
+        }
+        if frame::is_waiting() {
+            self.ctx.request_repaint();
+        }
+        if self.ctx.has_requested_repaint() {
+            rt_local_core::base::idle();
+        }
+        if let Some(deadline) = rt_local_core::base::next_deadline() {
+            self.ctx
+                .request_repaint_after(deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+impl Drop for RtLocalRuntime {
+    fn drop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+        rt_local_core::base::leave();
+        frame::leave();
+        viewport::leave();
+    }
+}
+
+struct EguiWake(Context);
+
+impl Wake for EguiWake {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !viewport::is_waking_viewport() {
+            self.0.request_repaint_of(ViewportId::ROOT);
+        }
+    }
+}
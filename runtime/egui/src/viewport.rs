@@ -0,0 +1,212 @@
+use egui::{Context, ViewportId};
+use rt_local_core::{
+    base::{poll_with, PollOptions},
+    spawn_local,
+    task::AbortHandle,
+    Task,
+};
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Poll, Wake, Waker},
+};
+
+struct ViewportTask {
+    viewport_id: ViewportId,
+    abort: AbortHandle,
+    frame: u64,
+}
+
+struct ViewportState {
+    ctx: Context,
+    tasks: Vec<ViewportTask>,
+    frame: u64,
+}
+
+thread_local! {
+    static VIEWPORTS: RefCell<Option<ViewportState>> = const { RefCell::new(None) };
+    static IS_WAKING_VIEWPORT: Cell<bool> = const { Cell::new(false) };
+    static VIEWPORT_POLLS: Cell<usize> = const { Cell::new(0) };
+}
+
+pub(crate) fn enter(ctx: &Context) {
+    VIEWPORTS.with(|v| {
+        *v.borrow_mut() = Some(ViewportState {
+            ctx: ctx.clone(),
+            tasks: Vec::new(),
+            frame: 0,
+        })
+    });
+}
+pub(crate) fn leave() {
+    let state = VIEWPORTS.with(|v| v.borrow_mut().take());
+    drop(state);
+}
+
+/// Poll the woken tasks at the start of a frame of a viewport other than the root viewport.
+///
+/// A woken task that is not bound to such a viewport may have been queued without a repaint of the root viewport,
+/// so the root viewport is repainted if one was polled.
+pub(crate) fn begin_frame(ctx: &Context) {
+    if ctx.viewport_id() == ViewportId::ROOT
+        || !VIEWPORTS.with(|v| v.borrow().as_ref().is_some_and(|s| &s.ctx == ctx))
+    {
+        return;
+    }
+    let old = VIEWPORT_POLLS.with(|p| p.replace(0));
+    let polls = poll_with(PollOptions::default()).polls;
+    let viewport_polls = VIEWPORT_POLLS.with(|p| p.replace(old));
+    if polls > viewport_polls {
+        ctx.request_repaint_of(ViewportId::ROOT);
+    }
+}
+
+/// Returns `true` while a task bound to a viewport other than the root viewport is being woken on the current thread.
+///
+/// The waker of the task has already requested a repaint of its viewport, so the runtime waker does not repaint the root viewport.
+pub(crate) fn is_waking_viewport() -> bool {
+    IS_WAKING_VIEWPORT.with(|w| w.get())
+}
+
+/// Cancel the tasks whose viewport is missing from the input of the root frame that has just ended.
+///
+/// The integration lists the open viewports in [`RawInput::viewports`](egui::RawInput::viewports),
+/// and a viewport shown for the first time is listed from the next frame,
+/// so tasks are kept during the root frame in which they were spawned.
+/// Tasks are not canceled if [`Context::embed_viewports`] is `true`, since embedded viewports are not listed.
+pub(crate) fn update(ctx: &Context) {
+    let is_embedded = ctx.embed_viewports();
+    let mut aborts = Vec::new();
+    VIEWPORTS.with(|v| {
+        let mut v = v.borrow_mut();
+        let Some(s) = v.as_mut() else {
+            return;
+        };
+        let frame = s.frame;
+        s.frame += 1;
+        s.tasks.retain(|t| {
+            if t.abort.is_finished() {
+                return false;
+            }
+            if is_embedded
+                || t.frame == frame
+                || ctx.input(|i| i.raw.viewports.contains_key(&t.viewport_id))
+            {
+                true
+            } else {
+                aborts.push(t.abort.clone());
+                false
+            }
+        });
+    });
+    for abort in aborts {
+        abort.abort();
+    }
+}
+
+/// Spawn a future bound to the viewport `viewport_id`.
+///
+/// Waking the task requests a repaint of that viewport with [`Context::request_repaint_of`], without repainting the root viewport.
+/// Woken tasks are polled at the start of the next frame of any viewport.
+///
+/// The task is canceled by [`RtLocalRuntime::after_update`](crate::RtLocalRuntime::after_update)
+/// once the integration no longer lists the viewport in [`RawInput::viewports`](egui::RawInput::viewports) of the root viewport,
+/// that is, after its parent stops showing it.
+/// Spawn the task in the frame that shows the viewport, or after it;
+/// a task whose viewport is not listed in the next root frame is canceled at its end.
+/// Tasks for [`ViewportId::ROOT`] are never canceled by this function,
+/// nor are any tasks while [`Context::embed_viewports`] is `true`.
+///
+/// # Panics
+///
+/// Panics if [`RtLocalRuntime`](crate::RtLocalRuntime) is not running on the current thread.
+#[track_caller]
+pub fn spawn_for_viewport<F: Future + 'static>(
+    viewport_id: ViewportId,
+    future: F,
+) -> Task<F::Output> {
+    let ctx = VIEWPORTS.with(|v| {
+        v.borrow()
+            .as_ref()
+            .expect("`RtLocalRuntime` is not running on the current thread")
+            .ctx
+            .clone()
+    });
+    let task = spawn_local(ViewportFuture {
+        future: Box::pin(future),
+        ctx,
+        viewport_id,
+        waker: None,
+    });
+    if viewport_id != ViewportId::ROOT {
+        let abort = task.abort_handle();
+        VIEWPORTS.with(|v| {
+            if let Some(s) = v.borrow_mut().as_mut() {
+                s.tasks.push(ViewportTask {
+                    viewport_id,
+                    abort,
+                    frame: s.frame,
+                });
+            }
+        });
+    }
+    task
+}
+
+struct ViewportFuture<F> {
+    future: Pin<Box<F>>,
+    ctx: Context,
+    viewport_id: ViewportId,
+    waker: Option<(Waker, Waker)>,
+}
+
+impl<F: Future> Future for ViewportFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.viewport_id != ViewportId::ROOT {
+            VIEWPORT_POLLS.with(|p| p.set(p.get() + 1));
+        }
+        let waker = match &this.waker {
+            Some((inner, waker)) if inner.will_wake(cx.waker()) => waker.clone(),
+            _ => {
+                let waker = Waker::from(Arc::new(ViewportWake {
+                    ctx: this.ctx.clone(),
+                    viewport_id: this.viewport_id,
+                    waker: cx.waker().clone(),
+                }));
+                this.waker = Some((cx.waker().clone(), waker.clone()));
+                waker
+            }
+        };
+        this.future
+            .as_mut()
+            .poll(&mut std::task::Context::from_waker(&waker))
+    }
+}
+
+struct ViewportWake {
+    ctx: Context,
+    viewport_id: ViewportId,
+    waker: Waker,
+}
+
+impl Wake for ViewportWake {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        if self.viewport_id == ViewportId::ROOT || self.ctx.embed_viewports() {
+            self.ctx.request_repaint_of(ViewportId::ROOT);
+            self.waker.wake_by_ref();
+            return;
+        }
+        self.ctx.request_repaint_of(self.viewport_id);
+        let old = IS_WAKING_VIEWPORT.with(|w| w.replace(true));
+        self.waker.wake_by_ref();
+        IS_WAKING_VIEWPORT.with(|w| w.set(old));
+    }
+}
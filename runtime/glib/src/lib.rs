@@ -0,0 +1,222 @@
+use glib::{idle_source_new, timeout_source_new, MainContext, Priority, Source};
+use rt_local_core::base::{idle, next_deadline, EventLoop};
+use std::{
+    cell::Cell,
+    future::Future,
+    marker::PhantomData,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::Wake,
+    time::{Duration, Instant},
+};
+
+/// Executes the specified future and blocks until it completes.
+///
+/// The thread-default [`MainContext`] is iterated while waiting, so GLib sources attached to it are dispatched.
+///
+/// # Panics
+///
+/// Panics if the thread-default [`MainContext`] is owned by another thread.
+pub fn run<T>(future: impl Future<Output = T>) -> T {
+    rt_local_core::base::run(&GlibEventLoop::new(), future)
+}
+
+struct GlibEventLoop {
+    context: MainContext,
+    deadline: Cell<Option<Instant>>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl GlibEventLoop {
+    fn new() -> Self {
+        Self {
+            context: MainContext::ref_thread_default(),
+            deadline: Cell::new(None),
+            _not_send: PhantomData,
+        }
+    }
+}
+impl EventLoop for GlibEventLoop {
+    fn waker(&self) -> std::task::Waker {
+        Arc::new(Waker(self.context.clone())).into()
+    }
+    fn run<T>(&self, mut poll: impl FnMut() -> ControlFlow<T>) -> T {
+        let _guard = self
+            .context
+            .acquire()
+            .expect("main context is owned by another thread");
+        loop {
+            if let ControlFlow::Break(value) = poll() {
+                return value;
+            }
+            if idle() {
+                continue;
+            }
+            if let Some(deadline) = self.deadline.get() {
+                let source = timeout_source(deadline, || glib::ControlFlow::Break);
+                source.attach(Some(&self.context));
+                self.context.iteration(true);
+                source.destroy();
+            } else {
+                self.context.iteration(true);
+            }
+        }
+    }
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+    }
+}
+
+fn timeout_source(
+    deadline: Instant,
+    f: impl FnMut() -> glib::ControlFlow + Send + 'static,
+) -> Source {
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    let timeout = Duration::from_millis(timeout.as_micros().div_ceil(1000) as u64);
+    timeout_source_new(timeout, None, Priority::DEFAULT, f)
+}
+
+struct Waker(MainContext);
+
+impl Wake for Waker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.wakeup();
+    }
+}
+
+/// Asynchronous runtime driven by a [`MainContext`] that is iterated by someone else,
+/// such as `gtk::Application::run` or [`glib::MainLoop::run`].
+///
+/// Tasks are polled from a source attached to the main context,
+/// and tasks waiting for [`wait_for_idle`](rt_local_core::wait_for_idle) are resumed from a source with [`Priority::DEFAULT_IDLE`].
+/// The runtime is stopped when `RtLocalRuntime` is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use glib::{MainContext, MainLoop};
+/// use rt_local_core::spawn_local;
+/// use rt_local_runtime_glib::RtLocalRuntime;
+///
+/// let context = MainContext::new();
+/// context
+///     .with_thread_default(|| {
+///         let _rt = RtLocalRuntime::new(&context);
+///         let main_loop = MainLoop::new(Some(&context), false);
+///         spawn_local({
+///             let main_loop = main_loop.clone();
+///             async move { main_loop.quit() }
+///         })
+///         .detach();
+///         main_loop.run();
+///     })
+///     .unwrap();
+/// ```
+pub struct RtLocalRuntime {
+    state: Arc<State>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+struct State {
+    context: MainContext,
+    is_alive: AtomicBool,
+    is_scheduled: AtomicBool,
+    is_idle_scheduled: AtomicBool,
+    timer: Mutex<Option<Source>>,
+}
+
+impl RtLocalRuntime {
+    /// Start the runtime on the current thread, driven by `context`.
+    ///
+    /// `context` must be iterated by the current thread.
+    pub fn new(context: &MainContext) -> Self {
+        let state = Arc::new(State {
+            context: context.clone(),
+            is_alive: AtomicBool::new(true),
+            is_scheduled: AtomicBool::new(false),
+            is_idle_scheduled: AtomicBool::new(false),
+            timer: Mutex::new(None),
+        });
+        rt_local_core::base::enter(Arc::new(SourceWaker(state.clone())).into());
+        state.schedule_poll();
+        Self {
+            state,
+            _not_send: PhantomData,
+        }
+    }
+}
+impl Drop for RtLocalRuntime {
+    fn drop(&mut self) {
+        self.state.is_alive.store(false, Ordering::SeqCst);
+        if let Some(timer) = self.state.timer.lock().unwrap().take() {
+            timer.destroy();
+        }
+        rt_local_core::base::leave();
+    }
+}
+
+impl State {
+    fn schedule_poll(self: &Arc<Self>) {
+        if self.is_scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let state = self.clone();
+        idle_source_new(None, Priority::DEFAULT, move || {
+            state.poll();
+            glib::ControlFlow::Break
+        })
+        .attach(Some(&self.context));
+    }
+    fn schedule_idle(self: &Arc<Self>) {
+        if self.is_idle_scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let state = self.clone();
+        idle_source_new(None, Priority::DEFAULT_IDLE, move || {
+            state.is_idle_scheduled.store(false, Ordering::SeqCst);
+            if state.is_alive.load(Ordering::SeqCst) && idle() {
+                state.schedule_poll();
+            }
+            glib::ControlFlow::Break
+        })
+        .attach(Some(&self.context));
+    }
+    fn poll(self: &Arc<Self>) {
+        self.is_scheduled.store(false, Ordering::SeqCst);
+        if !self.is_alive.load(Ordering::SeqCst) {
+            return;
+        }
+        rt_local_core::base::poll();
+        self.schedule_idle();
+
+        let timer = next_deadline().map(|deadline| {
+            let state = self.clone();
+            let source = timeout_source(deadline, move || {
+                state.schedule_poll();
+                glib::ControlFlow::Break
+            });
+            source.attach(Some(&self.context));
+            source
+        });
+        if let Some(old) = std::mem::replace(&mut *self.timer.lock().unwrap(), timer) {
+            old.destroy();
+        }
+    }
+}
+
+struct SourceWaker(Arc<State>);
+
+impl Wake for SourceWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.schedule_poll();
+    }
+}
@@ -0,0 +1,179 @@
+use rt_local_core::{
+    base::{idle, next_deadline},
+    spawn_local,
+};
+use slint::{invoke_from_event_loop, PlatformError, Timer, TimerMode};
+use std::{
+    cell::RefCell,
+    future::Future,
+    marker::PhantomData,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::Wake,
+    time::{Duration, Instant},
+};
+
+/// Executes the specified future on the Slint event loop and blocks until it completes.
+///
+/// The event loop is run with [`slint::run_event_loop_until_quit`], and is quit when the future completes.
+///
+/// # Panics
+///
+/// Panics if the event loop cannot be run, or if the event loop quits before the future completes.
+pub fn run<T: 'static>(future: impl Future<Output = T> + 'static) -> T {
+    let _rt = RtLocalRuntime::new();
+    let output = Rc::new(RefCell::new(None));
+    spawn_local({
+        let output = output.clone();
+        async move {
+            *output.borrow_mut() = Some(future.await);
+            let _ = slint::quit_event_loop();
+        }
+    })
+    .detach();
+    slint::run_event_loop_until_quit().expect("failed to run event loop");
+    let output = output.borrow_mut().take();
+    output.expect("event loop quit before the future completed")
+}
+
+/// Runs the Slint event loop with the runtime started, like [`slint::run_event_loop`].
+pub fn run_event_loop() -> Result<(), PlatformError> {
+    let _rt = RtLocalRuntime::new();
+    slint::run_event_loop()
+}
+
+/// Runs the Slint event loop with the runtime started, like [`slint::run_event_loop_until_quit`].
+pub fn run_event_loop_until_quit() -> Result<(), PlatformError> {
+    let _rt = RtLocalRuntime::new();
+    slint::run_event_loop_until_quit()
+}
+
+thread_local! {
+    static TIMER: RefCell<Option<Timer>> = const { RefCell::new(None) };
+}
+
+/// Asynchronous runtime driven by the Slint event loop.
+///
+/// Waking a task calls [`slint::invoke_from_event_loop`], and tasks are polled from the invoked function.
+/// Tasks waiting for [`wait_for_idle`](rt_local_core::wait_for_idle) are resumed after the events queued in the meantime have been processed.
+///
+/// Create it on the thread that runs the event loop, before calling [`slint::run_event_loop`].
+/// If the Slint platform is not initialized yet, tasks are first polled when the event loop starts.
+/// The runtime is stopped when `RtLocalRuntime` is dropped.
+/// [`run_event_loop`] and [`run_event_loop_until_quit`] do this for you.
+pub struct RtLocalRuntime {
+    state: Arc<State>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+struct State {
+    is_alive: AtomicBool,
+    is_scheduled: AtomicBool,
+    is_idle_scheduled: AtomicBool,
+}
+
+impl RtLocalRuntime {
+    /// Start the runtime on the current thread.
+    pub fn new() -> Self {
+        let state = Arc::new(State {
+            is_alive: AtomicBool::new(true),
+            is_scheduled: AtomicBool::new(false),
+            is_idle_scheduled: AtomicBool::new(false),
+        });
+        TIMER.with(|t| *t.borrow_mut() = Some(Timer::default()));
+        rt_local_core::base::enter(Arc::new(Waker(state.clone())).into());
+        state.schedule_poll();
+        Self {
+            state,
+            _not_send: PhantomData,
+        }
+    }
+}
+impl Default for RtLocalRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Drop for RtLocalRuntime {
+    fn drop(&mut self) {
+        self.state.is_alive.store(false, Ordering::SeqCst);
+        TIMER.with(|t| t.borrow_mut().take());
+        rt_local_core::base::leave();
+    }
+}
+
+impl State {
+    fn schedule_poll(self: &Arc<Self>) {
+        if self.is_scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let state = self.clone();
+        if invoke_from_event_loop(move || state.poll()).is_err() {
+            // The platform is not initialized yet, or the event loop has quit.
+            // On the runtime thread, fall back to a timer, which fires once the event loop runs.
+            // Wakes from other threads are picked up by that poll, since it processes all pending requests.
+            self.is_scheduled.store(false, Ordering::SeqCst);
+            let _ = TIMER.try_with(|t| {
+                if let Some(timer) = &*t.borrow() {
+                    let state = self.clone();
+                    timer.start(TimerMode::SingleShot, Duration::ZERO, move || state.poll());
+                }
+            });
+        }
+    }
+    fn schedule_idle(self: &Arc<Self>) {
+        if self.is_idle_scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let state = self.clone();
+        let r = invoke_from_event_loop(move || {
+            state.is_idle_scheduled.store(false, Ordering::SeqCst);
+            if state.is_alive.load(Ordering::SeqCst) && idle() {
+                state.schedule_poll();
+            }
+        });
+        if r.is_err() {
+            self.is_idle_scheduled.store(false, Ordering::SeqCst);
+        }
+    }
+    fn poll(self: &Arc<Self>) {
+        self.is_scheduled.store(false, Ordering::SeqCst);
+        if !self.is_alive.load(Ordering::SeqCst) {
+            return;
+        }
+        rt_local_core::base::poll();
+        self.schedule_idle();
+
+        let deadline = next_deadline();
+        TIMER.with(|t| {
+            let t = t.borrow();
+            let Some(timer) = &*t else {
+                return;
+            };
+            if let Some(deadline) = deadline {
+                let state = self.clone();
+                timer.start(
+                    TimerMode::SingleShot,
+                    deadline.saturating_duration_since(Instant::now()),
+                    move || state.schedule_poll(),
+                );
+            } else {
+                timer.stop();
+            }
+        });
+    }
+}
+
+struct Waker(Arc<State>);
+
+impl Wake for Waker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.schedule_poll();
+    }
+}
@@ -2,14 +2,15 @@
 
 use rt_local_core::base::{idle, EventLoop};
 use std::{
-    future::Future, marker::PhantomData, ops::ControlFlow, ptr::null_mut, sync::Arc, task::Wake,
+    cell::Cell, future::Future, marker::PhantomData, ops::ControlFlow, ptr::null_mut, sync::Arc,
+    task::Wake, time::Instant,
 };
 use windows::Win32::{
-    Foundation::{HWND, LPARAM, WPARAM},
-    System::Threading::GetCurrentThreadId,
+    Foundation::{FALSE, HWND, LPARAM, WPARAM},
+    System::Threading::{GetCurrentThreadId, INFINITE},
     UI::WindowsAndMessaging::{
-        DispatchMessageW, GetMessageW, PeekMessageW, PostThreadMessageW, TranslateMessage, MSG,
-        PM_REMOVE, WM_NULL, WM_QUIT,
+        DispatchMessageW, GetMessageW, MsgWaitForMultipleObjects, PeekMessageW, PostThreadMessageW,
+        TranslateMessage, MSG, PM_REMOVE, QS_ALLINPUT, WM_NULL, WM_QUIT,
     },
 };
 
@@ -20,6 +21,7 @@ pub fn run<T>(future: impl Future<Output = T>) -> T {
 
 struct WindowsEventLoop {
     waker: Arc<Waker>,
+    deadline: Cell<Option<Instant>>,
     _not_send: PhantomData<*mut ()>,
 }
 
@@ -29,6 +31,7 @@ impl WindowsEventLoop {
             let thread_id = GetCurrentThreadId();
             Self {
                 waker: Arc::new(Waker { thread_id }),
+                deadline: Cell::new(None),
                 _not_send: PhantomData,
             }
         }
@@ -48,6 +51,15 @@ impl EventLoop for WindowsEventLoop {
                 if !PeekMessageW(&mut msg, HWND(null_mut()), 0, 0, PM_REMOVE).as_bool() {
                     if idle() {
                         continue;
+                    } else if let Some(deadline) = self.deadline.get() {
+                        let timeout = deadline.saturating_duration_since(Instant::now());
+                        let timeout_ms = timeout
+                            .as_micros()
+                            .div_ceil(1000)
+                            .min((INFINITE - 1) as u128)
+                            as u32;
+                        let _ = MsgWaitForMultipleObjects(None, FALSE, timeout_ms, QS_ALLINPUT);
+                        continue;
                     } else {
                         GetMessageW(&mut msg, HWND(null_mut()), 0, 0).ok().unwrap();
                     }
@@ -60,6 +72,9 @@ impl EventLoop for WindowsEventLoop {
             }
         }
     }
+    fn set_deadline(&self, deadline: Option<Instant>) {
+        self.deadline.set(deadline);
+    }
 }
 
 struct Waker {
@@ -1,4 +1,8 @@
-pub use rt_local_core::{base, spawn_local, wait_for_idle, Task};
+pub use rt_local_core::{
+    base, scope, spawn_blocking, spawn_local, spawn_local_with_priority, spawn_thread, sync, task,
+    task_local, time, wait_for_idle, yield_now, BlockingTask, DispatchError, DispatchTask,
+    Dispatcher, LocalPool, Scope, Task,
+};
 
 /// Runtime implementations.
 pub mod runtime;
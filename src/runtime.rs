@@ -5,5 +5,27 @@ pub mod blocking;
 #[cfg(all(target_os = "windows", feature = "windows"))]
 pub mod windows;
 
+#[cfg(feature = "egui")]
+pub mod egui;
+
 #[cfg(feature = "eframe")]
 pub mod eframe;
+
+/// Runtime with [async-io](https://crates.io/crates/async-io) reactor.
+#[cfg(feature = "async-io")]
+pub mod async_io;
+
+/// Runtime with [GLib](https://crates.io/crates/glib) main context.
+#[cfg(feature = "glib")]
+pub mod glib;
+
+/// Runtime with [calloop](https://crates.io/crates/calloop) event loop.
+#[cfg(feature = "calloop")]
+pub mod calloop;
+
+#[cfg(feature = "bevy")]
+pub mod bevy;
+
+/// Runtime with [Slint](https://crates.io/crates/slint) event loop.
+#[cfg(feature = "slint")]
+pub mod slint;
@@ -0,0 +1,6 @@
+//! Runtime with [Bevy]. (game engine)
+//!
+//! [Bevy]: https://crates.io/crates/bevy
+//!
+//! Add [`RtLocalPlugin`] to the app, and spawn tasks from systems that take [`NonSend<RtLocalRuntime>`](https://docs.rs/bevy/latest/bevy/ecs/system/struct.NonSend.html).
+pub use rt_local_runtime_bevy::*;
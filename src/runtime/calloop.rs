@@ -0,0 +1,38 @@
+#![allow(clippy::test_attr_in_doctest)]
+
+pub use rt_local_runtime_calloop::*;
+
+/// Mark the asynchronous function as a the entry point.
+///
+/// The asynchronous runtime is launched using [`run`].
+/// # Examples
+///
+/// ```
+/// #[rt_local::runtime::calloop::main]
+/// async fn main() {
+///     // ...
+/// }
+/// ```
+pub use rt_local_macros::calloop_main as main;
+
+/// Mark the function as a test.
+///
+/// When specified for an asynchronous function, use [`run`] to launch the asynchronous runtime.
+/// When specified for a synchronous function, do not launch the asynchronous runtime.
+///
+/// # Examples
+///
+/// ```
+/// use rt_local::runtime::calloop::test;
+///
+/// #[test]
+/// async fn test_async() {
+///     // ...
+/// }
+///
+/// #[test]
+/// fn test_sync() {
+///     // ..
+/// }
+/// ```
+pub use rt_local_macros::calloop_test as test;
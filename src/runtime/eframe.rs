@@ -6,9 +6,14 @@
 //! To use a single-threaded asynchronous runtime in egui application, use one of the following methods.
 //!
 //! - use [`rt_local::runtime::eframe::run_simple_native`] instead of [`eframe::run_simple_native`].
+//! - use [`rt_local::runtime::eframe::run_native`] instead of [`eframe::run_native`].
+//! - use [`run_async_native`] with an [`AsyncApp`] for asynchronous setup and exit.
 //! - use [`RtLocalRuntime`] with [`eframe::run_native`]. (see [`RtLocalRuntime`] for details.)
 //!
+//! To use the runtime with other egui integrations, see [`runtime::egui`](crate::runtime::egui).
+//!
 //! [`rt_local::runtime::eframe::run_simple_native`]: crate::runtime::eframe::run_simple_native
+//! [`rt_local::runtime::eframe::run_native`]: crate::runtime::eframe::run_native
 //! [`eframe::run_simple_native`]: https://docs.rs/eframe/0.24.1/eframe/fn.run_simple_native.html
 //! [`eframe::run_native`]: https://docs.rs/eframe/0.24.1/eframe/fn.run_simple_native.html
 pub use rt_local_runtime_eframe::*;
@@ -0,0 +1,9 @@
+//! Runtime with [egui] context.
+//!
+//! [egui]: https://crates.io/crates/egui
+//!
+//! Use [`RtLocalRuntime`] to run tasks in any egui integration, such as egui-winit, egui_glow or bevy_egui.
+//! Call [`RtLocalRuntime::before_update`] and [`RtLocalRuntime::after_update`] around each frame.
+//!
+//! For [eframe](https://crates.io/crates/eframe) applications, use [`runtime::eframe`](crate::runtime::eframe) instead.
+pub use rt_local_runtime_egui::*;
@@ -0,0 +1,42 @@
+#![cfg(feature = "async-io")]
+use async_io::{Async, Timer};
+use rt_local::{runtime::async_io::run, spawn_local};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+mod test_utils;
+mod common {
+    mod tests;
+}
+
+#[test]
+fn test_async_io_timer() {
+    run(async {
+        let start = Instant::now();
+        spawn_local(Timer::after(Duration::from_millis(50))).await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    });
+}
+
+#[test]
+fn test_async_io_net() {
+    run(async {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0)).unwrap();
+        let addr = listener.get_ref().local_addr().unwrap();
+        let server = spawn_local(async move {
+            let (s, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 5];
+            let mut len = 0;
+            while len < buf.len() {
+                len += s.read_with(|s| (&*s).read(&mut buf[len..])).await.unwrap();
+            }
+            buf
+        });
+        let c = Async::<TcpStream>::connect(addr).await.unwrap();
+        c.write_with(|c| (&*c).write_all(b"hello")).await.unwrap();
+        assert_eq!(&server.await, b"hello");
+    });
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "bevy")]
+use bevy::{
+    app::{App, Startup, Update},
+    ecs::system::{NonSend, ResMut, Resource},
+    MinimalPlugins,
+};
+use rt_local::{
+    runtime::bevy::{RtLocalPlugin, RtLocalRuntime},
+    wait_for_idle, yield_now,
+};
+use std::{
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Resource, Default)]
+struct Frame(usize);
+
+fn count_frame(mut frame: ResMut<Frame>) {
+    frame.0 += 1;
+}
+
+#[test]
+fn test_bevy_plugin() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, RtLocalPlugin))
+        .init_resource::<Frame>()
+        .add_systems(Update, count_frame)
+        .add_systems(Startup, {
+            let log = log.clone();
+            move |rt: NonSend<RtLocalRuntime>| {
+                let log = log.clone();
+                rt.spawn_local(async move {
+                    let state = Rc::new(()); // `!Send` values can be held across `.await`
+                    log.lock().unwrap().push("start");
+                    yield_now().await;
+                    log.lock().unwrap().push("yield");
+                    wait_for_idle().await;
+                    log.lock().unwrap().push("idle");
+                    drop(state);
+                })
+                .detach();
+            }
+        });
+    app.update();
+    assert_eq!(*log.lock().unwrap(), ["start"]);
+    app.update();
+    app.update();
+    assert_eq!(*log.lock().unwrap(), ["start", "yield", "idle"]);
+    assert_eq!(app.world().resource::<Frame>().0, 3);
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "calloop")]
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    EventLoop,
+};
+use rt_local::{
+    runtime::calloop::{run, RtLocalRuntime},
+    spawn_local, wait_for_idle,
+};
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+mod test_utils;
+mod common {
+    mod tests;
+}
+
+#[test]
+fn test_calloop_runtime() {
+    let mut event_loop = EventLoop::<Vec<&str>>::try_new().unwrap();
+    let rt = RtLocalRuntime::new(&event_loop.handle()).unwrap();
+    event_loop
+        .handle()
+        .insert_source(
+            Timer::from_duration(Duration::from_millis(10)),
+            |_, _, log| {
+                log.push("timer");
+                TimeoutAction::Drop
+            },
+        )
+        .unwrap();
+    let value = Rc::new(Cell::new(0));
+    let signal = event_loop.get_signal();
+    spawn_local({
+        let value = value.clone();
+        async move {
+            rt_local::time::sleep(Duration::from_millis(20)).await;
+            value.set(1);
+            wait_for_idle().await;
+            value.set(2);
+            signal.stop();
+        }
+    })
+    .detach();
+    let mut log = Vec::new();
+    event_loop.run(None, &mut log, |_| {}).unwrap();
+    assert_eq!(value.get(), 2);
+    assert_eq!(log, ["timer"]);
+    drop(rt);
+}
@@ -2,7 +2,7 @@ use crate::run;
 use crate::test_utils::*;
 use async_std::task::sleep;
 use rt_local_core::*;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[test]
 fn test_run() {
@@ -95,3 +95,823 @@ fn test_wait_for_idle_many() {
     });
     p1.assert_ex(&[&["1-a"], &["2-a"], &["1-b", "2-b"]]);
 }
+
+#[test]
+fn test_time_sleep() {
+    let p = AssertPass::new();
+    run(async {
+        let p1 = p.clone();
+        let t = spawn_local(async move {
+            time::sleep(Duration::from_millis(200)).await;
+            p1.pass("2");
+        });
+        time::sleep(Duration::from_millis(100)).await;
+        p.pass("1");
+        t.await;
+        p.pass("3");
+    });
+    p.assert(&["1", "2", "3"]);
+}
+
+#[test]
+fn test_time_sleep_elapsed() {
+    let start = Instant::now();
+    run(async {
+        time::sleep(Duration::from_millis(100)).await;
+    });
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn test_time_timeout() {
+    run(async {
+        let r = time::timeout(
+            Duration::from_millis(50),
+            time::sleep(Duration::from_secs(10)),
+        )
+        .await;
+        assert!(r.is_err());
+        let r = time::timeout(Duration::from_secs(10), async { 1 }).await;
+        assert_eq!(r, Ok(1));
+    });
+}
+
+#[test]
+fn test_time_interval() {
+    let start = Instant::now();
+    run(async {
+        let mut interval = time::interval(Duration::from_millis(50));
+        for _ in 0..4 {
+            interval.tick().await;
+        }
+    });
+    assert!(start.elapsed() >= Duration::from_millis(150));
+}
+
+#[test]
+fn test_panic_join() {
+    run(async {
+        let line = line!() + 1;
+        let e = spawn_local(async { panic!("task panic") })
+            .join()
+            .await
+            .unwrap_err();
+        assert_eq!(e.location().line(), line);
+        assert_eq!(
+            e.panic_payload().unwrap().downcast_ref::<&str>(),
+            Some(&"task panic")
+        );
+    });
+}
+
+#[test]
+#[should_panic(expected = "task panic")]
+fn test_panic_await() {
+    run(async {
+        spawn_local(async { panic!("task panic") }).await;
+    });
+}
+
+#[test]
+fn test_panic_detach() {
+    let p = AssertPass::new();
+    run(async {
+        spawn_local(async { panic!("task panic") }).detach();
+        sleep(Duration::from_millis(100)).await;
+        p.pass("1");
+    });
+    p.assert(&["1"]);
+}
+
+#[test]
+fn test_task_cancel() {
+    let p = AssertPass::new();
+    run(async {
+        struct OnDrop(AssertPass);
+        impl Drop for OnDrop {
+            fn drop(&mut self) {
+                self.0.pass("drop");
+            }
+        }
+        let d = OnDrop(p.clone());
+        let t = spawn_local(async move {
+            let _d = d;
+            sleep(Duration::from_secs(10)).await;
+        });
+        wait_for_idle().await;
+        assert!(!t.is_finished());
+        assert_eq!(t.cancel().await, None);
+        p.pass("cancelled");
+    });
+    p.assert(&["drop", "cancelled"]);
+}
+
+#[test]
+fn test_task_cancel_completed() {
+    run(async {
+        let t = spawn_local(async { 1 });
+        wait_for_idle().await;
+        assert!(t.is_finished());
+        assert_eq!(t.cancel().await, Some(1));
+    });
+}
+
+#[test]
+fn test_abort_handle() {
+    run(async {
+        let t = spawn_local(async {
+            sleep(Duration::from_secs(10)).await;
+        });
+        let h = t.abort_handle();
+        h.clone().abort();
+        let e = t.join().await.unwrap_err();
+        assert!(e.is_cancelled());
+        assert!(h.is_finished());
+    });
+}
+
+#[test]
+fn test_task_poll_after_finished() {
+    use futures_core::FusedFuture;
+    use std::{future::Future, pin::pin, task::Context};
+
+    run(async {
+        let mut t = pin!(spawn_local(async { 1 }));
+        assert_eq!(t.as_mut().await, 1);
+        assert!(t.is_terminated());
+        std::future::poll_fn(|cx: &mut Context| {
+            assert!(t.as_mut().poll(cx).is_pending());
+            std::task::Poll::Ready(())
+        })
+        .await;
+    });
+}
+
+#[test]
+fn test_task_builder() {
+    use rt_local_core::task::{current_id, current_name, Builder};
+
+    run(async {
+        assert_eq!(current_id(), None);
+        let t = Builder::new()
+            .name("named")
+            .spawn_local(async { (current_id(), current_name()) });
+        let id = t.id();
+        assert_eq!(t.name(), Some("named"));
+        let (current, name) = t.await;
+        assert_eq!(current, Some(id));
+        assert_eq!(name.as_deref(), Some("named"));
+    });
+}
+
+#[test]
+fn test_task_builder_panic() {
+    use rt_local_core::task::Builder;
+
+    run(async {
+        let t = Builder::new()
+            .name("fetch-thumbnails")
+            .spawn_local(async { panic!("task panic") });
+        let id = t.id();
+        let e = t.join().await.unwrap_err();
+        assert_eq!(e.id(), id);
+        assert_eq!(e.name(), Some("fetch-thumbnails"));
+        assert!(e.to_string().contains("`fetch-thumbnails`"));
+    });
+}
+
+#[test]
+fn test_priority_order() {
+    use rt_local_core::task::Priority;
+
+    let p = AssertPass::new();
+    run(async {
+        let p1 = p.clone();
+        let p2 = p.clone();
+        let p3 = p.clone();
+        let t1 =
+            spawn_local_with_priority(Priority::Background, async move { p1.pass("background") });
+        let t2 = spawn_local_with_priority(Priority::Normal, async move { p2.pass("normal") });
+        let t3 = spawn_local_with_priority(Priority::High, async move { p3.pass("high") });
+        t1.await;
+        t2.await;
+        t3.await;
+    });
+    p.assert(&["high", "normal", "background"]);
+}
+
+#[test]
+fn test_priority_background_deferred() {
+    use rt_local_core::task::Priority;
+    use std::{future::poll_fn, task::Poll};
+
+    let p = AssertPass::new();
+    run(async {
+        let p1 = p.clone();
+        let p2 = p.clone();
+        let t1 =
+            spawn_local_with_priority(Priority::Background, async move { p1.pass("background") });
+        let t2 = spawn_local(async move {
+            for _ in 0..3 {
+                let mut yielded = false;
+                poll_fn(|cx| {
+                    if yielded {
+                        Poll::Ready(())
+                    } else {
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                })
+                .await;
+                p2.pass("normal");
+            }
+        });
+        t1.await;
+        t2.await;
+    });
+    p.assert(&["normal", "normal", "normal", "background"]);
+}
+
+rt_local_core::task_local! {
+    static TASK_LOCAL: u32;
+}
+
+#[test]
+fn test_task_local() {
+    run(async {
+        assert!(TASK_LOCAL.try_with(|_| ()).is_err());
+        TASK_LOCAL
+            .scope(1, async {
+                assert_eq!(TASK_LOCAL.get(), 1);
+                sleep(Duration::from_millis(10)).await;
+                assert_eq!(TASK_LOCAL.get(), 1);
+                TASK_LOCAL
+                    .scope(2, async {
+                        assert_eq!(TASK_LOCAL.get(), 2);
+                    })
+                    .await;
+                assert_eq!(TASK_LOCAL.get(), 1);
+                let t = spawn_local(async { TASK_LOCAL.try_with(|&v| v).ok() });
+                assert_eq!(t.await, None);
+            })
+            .await;
+        assert!(TASK_LOCAL.try_with(|_| ()).is_err());
+    });
+}
+
+#[test]
+fn test_task_local_inherit() {
+    run(async {
+        TASK_LOCAL
+            .inherited_scope(1, async {
+                let t = spawn_local(async {
+                    sleep(Duration::from_millis(10)).await;
+                    let t = spawn_local(async { TASK_LOCAL.get() });
+                    (TASK_LOCAL.get(), t.await)
+                });
+                assert_eq!(t.await, (1, 1));
+            })
+            .await;
+    });
+}
+
+#[test]
+fn test_yield_now() {
+    let p = AssertPass::new();
+    run(async {
+        let p1 = p.clone();
+        let p2 = p.clone();
+        let t1 = spawn_local(async move {
+            p1.pass("1-a");
+            yield_now().await;
+            p1.pass("1-b");
+        });
+        let t2 = spawn_local(async move {
+            p2.pass("2-a");
+            yield_now().await;
+            p2.pass("2-b");
+        });
+        t1.await;
+        t2.await;
+    });
+    p.assert(&["1-a", "2-a", "1-b", "2-b"]);
+}
+
+#[test]
+fn test_coop_budget() {
+    use rt_local_core::task::{consume_budget, set_coop_budget};
+    use std::{cell::Cell, rc::Rc};
+
+    run(async {
+        set_coop_budget(Some(16));
+        let stop = Rc::new(Cell::new(false));
+        let stop1 = stop.clone();
+        let t = spawn_local(async move {
+            let mut n = 0;
+            while !stop1.get() {
+                consume_budget().await;
+                n += 1;
+            }
+            n
+        });
+        let _s = spawn_local(async move { stop.set(true) });
+        assert!(t.await >= 16);
+    });
+}
+
+#[test]
+fn test_coop_budget_ready_channel() {
+    use rt_local_core::task::set_coop_budget;
+    use std::{cell::Cell, rc::Rc};
+
+    run(async {
+        set_coop_budget(Some(16));
+        let stop = Rc::new(Cell::new(false));
+        let stop1 = stop.clone();
+        let t = spawn_local(async move {
+            let (tx, mut rx) = sync::mpsc::unbounded_channel();
+            let mut n = 0;
+            while !stop1.get() && n < 100_000 {
+                tx.send(n).unwrap();
+                rx.recv().await.unwrap();
+                n += 1;
+            }
+            stop1.get()
+        });
+        let _s = spawn_local(async move { stop.set(true) });
+        assert!(t.await);
+    });
+}
+
+#[test]
+fn test_scope() {
+    run(async {
+        let data = vec![1, 2, 3];
+        let data = &data;
+        let sum = scope(|s| async move {
+            let a = s.spawn(async move {
+                sleep(Duration::from_millis(10)).await;
+                data[0] + data[1]
+            });
+            let b = s.spawn(async move { data[2] });
+            a.await + b.await
+        })
+        .await;
+        assert_eq!(sum, 6);
+    });
+}
+
+#[test]
+fn test_scope_wait_detached() {
+    let p = AssertPass::new();
+    run(async {
+        let p = &p;
+        scope(|s| async move {
+            s.spawn(async move {
+                sleep(Duration::from_millis(50)).await;
+                p.pass("child");
+            })
+            .detach();
+            p.pass("body");
+        })
+        .await;
+        p.pass("end");
+    });
+    p.assert(&["body", "child", "end"]);
+}
+
+#[test]
+fn test_scope_cancel_on_drop() {
+    let p = AssertPass::new();
+    run(async {
+        let p = &p;
+        let r = time::timeout(
+            Duration::from_millis(50),
+            scope(|s| async move {
+                s.spawn(async move {
+                    sleep(Duration::from_secs(10)).await;
+                    p.pass("child");
+                })
+                .detach();
+            }),
+        )
+        .await;
+        assert!(r.is_err());
+        p.pass("end");
+    });
+    p.assert(&["end"]);
+}
+
+#[test]
+fn test_join_set() {
+    run(async {
+        let mut set = task::LocalJoinSet::new();
+        for ms in [60, 20, 40] {
+            set.spawn(async move {
+                sleep(Duration::from_millis(ms)).await;
+                ms
+            });
+        }
+        assert_eq!(set.len(), 3);
+        let mut values = Vec::new();
+        while let Some(value) = set.join_next().await {
+            values.push(value.unwrap());
+        }
+        assert_eq!(values, [20, 40, 60]);
+        assert!(set.is_empty());
+    });
+}
+
+#[test]
+fn test_join_set_abort_all() {
+    let p = AssertPass::new();
+    run(async {
+        let mut set = task::LocalJoinSet::new();
+        for _ in 0..2 {
+            let p = p.clone();
+            set.spawn(async move {
+                sleep(Duration::from_secs(10)).await;
+                p.pass("child");
+            });
+        }
+        set.abort_all();
+        assert_eq!(set.len(), 2);
+        while let Some(value) = set.join_next().await {
+            assert!(value.unwrap_err().is_cancelled());
+        }
+        p.pass("end");
+    });
+    p.assert(&["end"]);
+}
+
+#[test]
+fn test_mpsc_bounded_fifo() {
+    run(async {
+        let (tx, mut rx) = sync::mpsc::channel(1);
+        let mut tasks = Vec::new();
+        for i in 0..3 {
+            let tx = tx.clone();
+            tasks.push(spawn_local(async move { tx.send(i).await.unwrap() }));
+        }
+        drop(tx);
+        let mut values = Vec::new();
+        while let Some(value) = rx.recv().await {
+            values.push(value);
+        }
+        assert_eq!(values, [0, 1, 2]);
+        for task in tasks {
+            task.await;
+        }
+    });
+}
+
+#[test]
+fn test_mpsc_unbounded_stream() {
+    run(async {
+        let (tx, mut rx) = sync::mpsc::unbounded_channel();
+        spawn_local(async move {
+            for i in 0..3 {
+                tx.send(i).unwrap();
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .detach();
+        let mut values = Vec::new();
+        while let Some(value) = std::future::poll_fn(|cx| {
+            futures_core::Stream::poll_next(std::pin::Pin::new(&mut rx), cx)
+        })
+        .await
+        {
+            values.push(value);
+        }
+        assert_eq!(values, [0, 1, 2]);
+    });
+}
+
+#[test]
+fn test_oneshot_closed() {
+    run(async {
+        let (tx, rx) = sync::oneshot::channel::<u32>();
+        drop(tx);
+        assert!(rx.await.is_err());
+        let (tx, rx) = sync::oneshot::channel();
+        drop(rx);
+        assert_eq!(tx.send(1), Err(1));
+    });
+}
+
+#[test]
+fn test_watch() {
+    run(async {
+        let (tx, mut rx) = sync::watch::channel(0);
+        let task = spawn_local(async move {
+            let mut values = Vec::new();
+            while rx.changed().await.is_ok() {
+                values.push(*rx.borrow_and_update());
+            }
+            values
+        });
+        for i in 1..=3 {
+            sleep(Duration::from_millis(10)).await;
+            tx.send(i).unwrap();
+        }
+        sleep(Duration::from_millis(10)).await;
+        drop(tx);
+        assert_eq!(task.await, [1, 2, 3]);
+    });
+}
+
+#[test]
+fn test_broadcast_lagged() {
+    run(async {
+        let (tx, mut rx) = sync::broadcast::channel(2);
+        for i in 0..4 {
+            tx.send(i).unwrap();
+        }
+        assert_eq!(rx.recv().await, Err(sync::broadcast::RecvError::Lagged(2)));
+        assert_eq!(rx.recv().await, Ok(2));
+        assert_eq!(rx.recv().await, Ok(3));
+        drop(tx);
+        assert_eq!(rx.recv().await, Err(sync::broadcast::RecvError::Closed));
+    });
+}
+
+#[test]
+fn test_mutex_across_await() {
+    let p = AssertPass::new();
+    run(async {
+        let m = std::rc::Rc::new(sync::LocalMutex::new(0));
+        let mut tasks = Vec::new();
+        for name in ["a", "b"] {
+            let m = m.clone();
+            let p = p.clone();
+            tasks.push(spawn_local(async move {
+                let mut g = m.lock().await;
+                p.pass(name);
+                sleep(Duration::from_millis(20)).await;
+                *g += 1;
+                p.pass(name);
+            }));
+        }
+        for task in tasks {
+            task.await;
+        }
+        assert_eq!(*m.lock().await, 2);
+    });
+    p.assert(&["a", "a", "b", "b"]);
+}
+
+#[test]
+fn test_rwlock_writer_not_starved() {
+    let p = AssertPass::new();
+    run(async {
+        let lock = std::rc::Rc::new(sync::LocalRwLock::new(()));
+        let r = lock.read().await;
+        let w = spawn_local({
+            let lock = lock.clone();
+            let p = p.clone();
+            async move {
+                let _w = lock.write().await;
+                p.pass("write");
+            }
+        });
+        yield_now().await;
+        let r2 = spawn_local({
+            let lock = lock.clone();
+            let p = p.clone();
+            async move {
+                let _r = lock.read().await;
+                p.pass("read");
+            }
+        });
+        yield_now().await;
+        drop(r);
+        w.await;
+        r2.await;
+    });
+    p.assert(&["write", "read"]);
+}
+
+#[test]
+fn test_semaphore_cancel_acquire() {
+    run(async {
+        let sem = sync::LocalSemaphore::new(1);
+        let permit = sem.acquire().await;
+        let r = time::timeout(Duration::from_millis(10), sem.acquire()).await;
+        assert!(r.is_err());
+        drop(permit);
+        assert_eq!(sem.available_permits(), 1);
+        let _permit = sem.acquire().await;
+        assert!(sem.try_acquire().is_err());
+    });
+}
+
+#[test]
+fn test_notify() {
+    run(async {
+        let n = std::rc::Rc::new(sync::LocalNotify::new());
+        n.notify_one();
+        n.notified().await;
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let n = n.clone();
+                spawn_local(async move { n.notified().await })
+            })
+            .collect();
+        sleep(Duration::from_millis(10)).await;
+        n.notify_waiters();
+        for task in tasks {
+            task.await;
+        }
+    });
+}
+
+#[test]
+fn test_notify_waiters_then_notify_one() {
+    use std::future::Future;
+
+    run(async {
+        let n = std::rc::Rc::new(sync::LocalNotify::new());
+        let mut released = std::pin::pin!(n.notified());
+        std::future::poll_fn(|cx| {
+            assert!(released.as_mut().poll(cx).is_pending());
+            std::task::Poll::Ready(())
+        })
+        .await;
+        n.notify_waiters();
+        let task = spawn_local({
+            let n = n.clone();
+            async move { n.notified().await }
+        });
+        sleep(Duration::from_millis(10)).await;
+        n.notify_one();
+        time::timeout(Duration::from_secs(1), task).await.unwrap();
+        released.await;
+    });
+}
+
+#[test]
+fn test_barrier() {
+    let p = AssertPass::new();
+    run(async {
+        let b = std::rc::Rc::new(sync::LocalBarrier::new(2));
+        let task = spawn_local({
+            let b = b.clone();
+            let p = p.clone();
+            async move {
+                p.pass("a");
+                b.wait().await;
+                p.pass("c");
+            }
+        });
+        sleep(Duration::from_millis(10)).await;
+        p.pass("b");
+        b.wait().await;
+        task.await;
+    });
+    p.assert(&["a", "b", "c"]);
+}
+
+#[test]
+fn test_dispatcher_dispatch() {
+    let p = AssertPass::new();
+    run(async {
+        let d = Dispatcher::current();
+        let p = p.clone();
+        std::thread::spawn(move || {
+            d.dispatch(move || {
+                assert!(task::current_id().is_some());
+                p.pass("dispatch");
+            })
+            .unwrap();
+        })
+        .join()
+        .unwrap();
+        sleep(Duration::from_millis(10)).await;
+    });
+    p.assert(&["dispatch"]);
+}
+
+#[test]
+fn test_dispatcher_spawn() {
+    run(async {
+        let d = Dispatcher::current();
+        let t = std::thread::spawn(move || {
+            d.spawn(|| async {
+                let value = std::rc::Rc::new(5);
+                sleep(Duration::from_millis(10)).await;
+                *value * 2
+            })
+        })
+        .join()
+        .unwrap();
+        assert_eq!(t.await.unwrap(), 10);
+    });
+}
+
+#[test]
+fn test_dispatcher_invoke() {
+    run(async {
+        let d = Dispatcher::current();
+        let id = std::thread::current().id();
+        let t = std::thread::spawn(move || d.invoke(|| std::thread::current().id()).unwrap());
+        while !t.is_finished() {
+            sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(t.join().unwrap(), id);
+    });
+}
+
+#[test]
+#[should_panic(expected = "`Dispatcher::invoke` called on the runtime thread")]
+fn test_dispatcher_invoke_in_task() {
+    run(async {
+        let d = Dispatcher::current();
+        spawn_local(async move {
+            let _ = d.invoke(|| {});
+        })
+        .await;
+    });
+}
+
+#[test]
+fn test_dispatcher_after_stop() {
+    let d = run(async { Dispatcher::current() });
+    assert!(d.dispatch(|| {}).is_err());
+    assert!(d.invoke(|| {}).is_err());
+    assert!(async_std::task::block_on(d.spawn(|| async {})).is_err());
+}
+
+#[test]
+fn test_spawn_blocking() {
+    run(async {
+        let id = std::thread::current().id();
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                spawn_blocking(move || {
+                    std::thread::sleep(Duration::from_millis(10));
+                    (i, std::thread::current().id())
+                })
+            })
+            .collect();
+        for (i, task) in tasks.into_iter().enumerate() {
+            let (value, thread_id) = task.await.unwrap();
+            assert_eq!(value, i);
+            assert_ne!(thread_id, id);
+        }
+        assert_eq!(std::thread::current().id(), id);
+    });
+}
+
+#[test]
+fn test_spawn_thread_panic() {
+    run(async {
+        let e = spawn_thread(|| -> u32 { panic!("decode failed") })
+            .await
+            .unwrap_err();
+        assert!(e.is_panic());
+        assert!(e.to_string().contains("decode failed"));
+    });
+}
+
+#[test]
+fn test_local_pool() {
+    run(async {
+        let pool = LocalPool::new(2);
+        let threads: Vec<_> = (0..2)
+            .map(|i| pool.spawn_on(i, || async { std::thread::current().id() }))
+            .collect();
+        let mut ids = Vec::new();
+        for t in threads {
+            ids.push(t.await.unwrap());
+        }
+        assert_ne!(ids[0], ids[1]);
+
+        let long = pool.spawn_pinned(|| async {
+            sleep(Duration::from_millis(50)).await;
+            std::thread::current().id()
+        });
+        sleep(Duration::from_millis(10)).await;
+        let short = pool.spawn_pinned(|| async { std::thread::current().id() });
+        assert_ne!(short.await.unwrap(), long.await.unwrap());
+        pool.shutdown().await;
+    });
+}
+
+#[test]
+fn test_local_pool_shutdown_waits() {
+    let p = AssertPass::new();
+    run(async {
+        let pool = LocalPool::new(1);
+        let p2 = p.clone();
+        pool.spawn_pinned(move || async move {
+            sleep(Duration::from_millis(30)).await;
+            p2.pass("task");
+        })
+        .detach();
+        pool.shutdown().await;
+        p.pass("shutdown");
+    });
+    p.assert(&["task", "shutdown"]);
+}
@@ -1,5 +1,9 @@
 use rt_local::{runtime::blocking::run, spawn_local, wait_for_idle, Task};
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    sync::Arc,
+    task::{Wake, Waker},
+};
 
 mod test_utils;
 mod common {
@@ -17,6 +21,14 @@ fn assert_counter(value: usize) {
     assert_eq!(COUNTER.with(|c| *c.borrow()), value);
 }
 
+fn noop_waker() -> Waker {
+    struct NoopWake;
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+    Arc::new(NoopWake).into()
+}
+
 #[test]
 fn run_repeat() {
     fn spawn_local_increment() -> Task<()> {
@@ -41,3 +53,84 @@ fn run_repeat() {
     });
     assert_counter(5);
 }
+
+#[test]
+fn poll_with_max_polls() {
+    use rt_local::base::{enter, leave, poll_with, PollOptions};
+
+    COUNTER.with(|c| *c.borrow_mut() = 0);
+    enter(noop_waker());
+    let tasks: Vec<_> = (0..3)
+        .map(|_| {
+            spawn_local(async {
+                increment();
+            })
+        })
+        .collect();
+    let r = poll_with(PollOptions {
+        max_polls: Some(2),
+        ..PollOptions::default()
+    });
+    assert_eq!(r.polls, 2);
+    assert!(r.remaining);
+    assert_counter(2);
+    let r = poll_with(PollOptions::default());
+    assert_eq!(r.polls, 1);
+    assert!(!r.remaining);
+    assert_counter(3);
+    drop(tasks);
+    leave();
+}
+
+#[test]
+fn poll_with_max_polls_drop_queued_task() {
+    use rt_local::{
+        base::{enter, leave, poll_with, PollOptions},
+        yield_now,
+    };
+
+    fn poll_max(max_polls: usize) {
+        poll_with(PollOptions {
+            max_polls: Some(max_polls),
+            ..PollOptions::default()
+        });
+    }
+
+    enter(noop_waker());
+    let t = spawn_local(async {
+        loop {
+            yield_now().await;
+        }
+    });
+    poll_max(1);
+    drop(t);
+    poll_max(1);
+    poll_max(0);
+    poll_max(1);
+    leave();
+}
+
+#[test]
+fn poll_with_drain_until_stable() {
+    use rt_local::base::{enter, leave, poll, poll_with, PollOptions};
+
+    COUNTER.with(|c| *c.borrow_mut() = 0);
+    enter(noop_waker());
+    let t = spawn_local(async {
+        increment();
+        spawn_local(async {
+            increment();
+        })
+        .await;
+    });
+    poll();
+    assert_counter(1);
+    let r = poll_with(PollOptions {
+        drain_until_stable: true,
+        ..PollOptions::default()
+    });
+    assert!(!r.remaining);
+    assert_counter(2);
+    drop(t);
+    leave();
+}
@@ -0,0 +1,290 @@
+#![cfg(feature = "egui")]
+use egui::{
+    Context, RawInput, ViewportBuilder, ViewportCommand, ViewportEvent, ViewportId, ViewportInfo,
+};
+use rt_local::{
+    runtime::egui::{
+        after_frame, before_frame, next_frame, spawn_for_viewport, ExitGuard, RtLocalRuntime,
+    },
+    spawn_local,
+    sync::{mpsc::unbounded_channel, oneshot},
+    time::sleep,
+    wait_for_idle, Task,
+};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+fn frame(rt: &RtLocalRuntime) {
+    rt.before_update();
+    let _ = rt.ctx().run(RawInput::default(), |_| {});
+    rt.after_update();
+}
+
+#[test]
+fn test_egui_runtime() {
+    let ctx = Context::default();
+    let rt = RtLocalRuntime::new(&ctx);
+    let value = Rc::new(Cell::new(0));
+    let task = spawn_local({
+        let value = value.clone();
+        async move {
+            value.set(1);
+            wait_for_idle().await;
+            value.set(2);
+            sleep(Duration::from_millis(20)).await;
+            value.set(3);
+        }
+    });
+    assert!(ctx.has_requested_repaint());
+    frame(&rt);
+    assert_eq!(value.get(), 1);
+    frame(&rt);
+    assert_eq!(value.get(), 2);
+    assert!(ctx.has_requested_repaint());
+
+    std::thread::sleep(Duration::from_millis(30));
+    frame(&rt);
+    assert_eq!(value.get(), 3);
+    assert!(task.is_finished());
+}
+
+#[test]
+fn test_egui_frame_futures() {
+    let ctx = Context::default();
+    let rt = RtLocalRuntime::new(&ctx);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let task = spawn_local({
+        let log = log.clone();
+        async move {
+            log.borrow_mut().push("start");
+            after_frame().await;
+            log.borrow_mut().push("after 1");
+            before_frame().await;
+            log.borrow_mut().push("before 2");
+            after_frame().await;
+            log.borrow_mut().push("after 2");
+            after_frame().await;
+            log.borrow_mut().push("after 3");
+            let t = next_frame().await;
+            assert!(t.delta > Duration::ZERO);
+            log.borrow_mut().push("before 4");
+        }
+    });
+    let frame_with_log = |name: &'static str| {
+        rt.before_update();
+        log.borrow_mut().push(name);
+        let _ = ctx.run(RawInput::default(), |_| {});
+        rt.after_update();
+    };
+    frame_with_log("update 1");
+    frame_with_log("update 2");
+    assert!(ctx.has_requested_repaint());
+    frame_with_log("update 3");
+    frame_with_log("update 4");
+    assert_eq!(
+        *log.borrow(),
+        [
+            "start", "update 1", "after 1", "before 2", "update 2", "after 2", "update 3",
+            "after 3", "before 4", "update 4"
+        ]
+    );
+    assert!(task.is_finished());
+    frame(&rt);
+    assert!(!ctx.has_requested_repaint());
+}
+
+#[test]
+fn test_egui_next_frame_waits_after_first_poll() {
+    let ctx = Context::default();
+    let rt = RtLocalRuntime::new(&ctx);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let before = before_frame();
+    let next = next_frame();
+    frame(&rt);
+    let task = spawn_local({
+        let log = log.clone();
+        async move {
+            before.await;
+            log.borrow_mut().push("before_frame");
+            next.await;
+            log.borrow_mut().push("next_frame");
+        }
+    });
+    frame(&rt);
+    assert_eq!(*log.borrow(), ["before_frame"]);
+    frame(&rt);
+    assert_eq!(*log.borrow(), ["before_frame", "next_frame"]);
+    assert!(task.is_finished());
+}
+
+#[test]
+fn test_egui_spawn_for_viewport() {
+    let ctx = Context::default();
+    ctx.set_embed_viewports(false);
+    let rt = RtLocalRuntime::new(&ctx);
+    let id = ViewportId::from_hash_of("child");
+    // Like eframe, list the child viewport in the input from the frame after it is first shown.
+    let is_listed = Cell::new(false);
+    let root_frame = |show_child: bool| {
+        let mut input = RawInput::default();
+        if is_listed.get() {
+            input.viewports.insert(id, ViewportInfo::default());
+        }
+        rt.before_update();
+        let _ = ctx.run(input, |ctx| {
+            if show_child {
+                ctx.show_viewport_deferred(id, ViewportBuilder::default(), |_, _| {});
+            }
+        });
+        rt.after_update();
+        is_listed.set(show_child);
+    };
+    let child_frame = || {
+        let input = RawInput {
+            viewport_id: id,
+            viewports: [(id, ViewportInfo::default())].into_iter().collect(),
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |_| {});
+    };
+    let count = Rc::new(Cell::new(0));
+    let (tx, mut rx) = unbounded_channel::<()>();
+    let task = spawn_for_viewport(id, {
+        let count = count.clone();
+        async move {
+            while rx.recv().await.is_some() {
+                count.set(count.get() + 1);
+            }
+        }
+    });
+    let (_root_tx, root_rx) = oneshot::channel::<()>();
+    let root_task = spawn_for_viewport(ViewportId::ROOT, async move {
+        let _ = root_rx.await;
+    });
+    let (_hidden_tx, hidden_rx) = oneshot::channel::<()>();
+    let hidden_task = spawn_for_viewport(ViewportId::from_hash_of("hidden"), async move {
+        let _ = hidden_rx.await;
+    });
+    for _ in 0..4 {
+        root_frame(true);
+    }
+    assert!(hidden_task.is_finished());
+    assert!(!ctx.has_requested_repaint_for(&ViewportId::ROOT));
+
+    // Waking the task repaints only its viewport, where it is polled.
+    tx.send(()).unwrap();
+    assert!(ctx.has_requested_repaint_for(&id));
+    assert!(!ctx.has_requested_repaint_for(&ViewportId::ROOT));
+    child_frame();
+    assert_eq!(count.get(), 1);
+    assert!(!ctx.has_requested_repaint_for(&ViewportId::ROOT));
+
+    // A task woken behind a viewport task still repaints the root viewport.
+    let other_count = Rc::new(Cell::new(0));
+    let (other_tx, mut other_rx) = unbounded_channel::<()>();
+    spawn_local({
+        let other_count = other_count.clone();
+        async move {
+            while other_rx.recv().await.is_some() {
+                other_count.set(other_count.get() + 1);
+            }
+        }
+    })
+    .detach();
+    for _ in 0..3 {
+        root_frame(true);
+    }
+    tx.send(()).unwrap();
+    other_tx.send(()).unwrap();
+    assert!(!ctx.has_requested_repaint_for(&ViewportId::ROOT));
+    child_frame();
+    assert_eq!(count.get(), 2);
+    assert_eq!(other_count.get(), 1);
+    assert!(ctx.has_requested_repaint_for(&ViewportId::ROOT));
+
+    // The task is canceled once the integration no longer lists the viewport.
+    root_frame(false);
+    assert!(!task.is_finished());
+    root_frame(false);
+    root_frame(false);
+    assert!(task.is_finished());
+    assert!(!root_task.is_finished());
+}
+
+fn exit_frame(
+    rt: &RtLocalRuntime,
+    exit: &mut ExitGuard,
+    close_requested: bool,
+    spawn_exit: impl FnOnce() -> Task<()>,
+) -> Vec<ViewportCommand> {
+    let mut input = RawInput::default();
+    if close_requested {
+        let info = ViewportInfo {
+            events: vec![ViewportEvent::Close],
+            ..Default::default()
+        };
+        input.viewports.insert(ViewportId::ROOT, info);
+    }
+    rt.before_update();
+    let mut output = rt.ctx().run(input, |ctx| exit.update(ctx, spawn_exit));
+    rt.after_update();
+    output
+        .viewport_output
+        .remove(&ViewportId::ROOT)
+        .map(|o| o.commands)
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_egui_exit_guard() {
+    let ctx = Context::default();
+    let rt = RtLocalRuntime::new(&ctx);
+    let mut exit = ExitGuard::new();
+    let (tx, rx) = oneshot::channel::<()>();
+    let mut rx = Some(rx);
+    let mut spawn_exit = || {
+        let rx = rx.take().expect("exit task spawned twice");
+        spawn_local(async move {
+            let _ = rx.await;
+        })
+    };
+
+    assert!(exit_frame(&rt, &mut exit, false, &mut spawn_exit).is_empty());
+    assert!(!exit.is_exiting());
+
+    // The close is canceled while the exit task is pending.
+    let commands = exit_frame(&rt, &mut exit, true, &mut spawn_exit);
+    assert_eq!(commands, [ViewportCommand::CancelClose]);
+    assert!(exit.is_exiting());
+    assert!(exit_frame(&rt, &mut exit, false, &mut spawn_exit).is_empty());
+
+    // A second close request during exiting is canceled again, without spawning another task.
+    let commands = exit_frame(&rt, &mut exit, true, &mut spawn_exit);
+    assert_eq!(commands, [ViewportCommand::CancelClose]);
+    assert!(exit.is_exiting());
+
+    // The close is sent again after the task finishes.
+    tx.send(()).unwrap();
+    let commands = exit_frame(&rt, &mut exit, false, &mut spawn_exit);
+    assert_eq!(commands, [ViewportCommand::Close]);
+    assert!(exit.is_exited());
+
+    // The close request caused by the command is not canceled.
+    assert!(exit_frame(&rt, &mut exit, true, &mut spawn_exit).is_empty());
+}
+
+#[test]
+fn test_egui_exit_guard_panic() {
+    let ctx = Context::default();
+    let rt = RtLocalRuntime::new(&ctx);
+    let mut exit = ExitGuard::new();
+    let spawn_exit = || spawn_local(async { panic!("exit task panic") });
+    let commands = exit_frame(&rt, &mut exit, true, spawn_exit);
+    assert_eq!(commands, [ViewportCommand::CancelClose]);
+    let commands = exit_frame(&rt, &mut exit, false, spawn_exit);
+    assert_eq!(commands, [ViewportCommand::Close]);
+    assert!(exit.is_exited());
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "glib")]
+use glib::{timeout_future, MainContext, MainLoop};
+use rt_local::{runtime::glib::RtLocalRuntime, spawn_local, wait_for_idle};
+use std::{
+    cell::Cell,
+    future::Future,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+mod test_utils;
+mod common {
+    mod tests;
+}
+
+/// Run on a new main context, since the global default context can be owned by only one test thread.
+fn run<T>(future: impl Future<Output = T>) -> T {
+    MainContext::new()
+        .with_thread_default(|| rt_local::runtime::glib::run(future))
+        .unwrap()
+}
+
+#[test]
+fn test_glib_timeout_future() {
+    run(async {
+        let start = Instant::now();
+        spawn_local(timeout_future(Duration::from_millis(50))).await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    });
+}
+
+#[test]
+fn test_glib_runtime() {
+    let context = MainContext::new();
+    context
+        .with_thread_default(|| {
+            let rt = RtLocalRuntime::new(&context);
+            let main_loop = MainLoop::new(Some(&context), false);
+            let value = Rc::new(Cell::new(0));
+            spawn_local({
+                let main_loop = main_loop.clone();
+                let value = value.clone();
+                async move {
+                    rt_local::time::sleep(Duration::from_millis(20)).await;
+                    value.set(1);
+                    wait_for_idle().await;
+                    value.set(2);
+                    main_loop.quit();
+                }
+            })
+            .detach();
+            main_loop.run();
+            assert_eq!(value.get(), 2);
+            drop(rt);
+        })
+        .unwrap();
+}
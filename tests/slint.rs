@@ -0,0 +1,55 @@
+#![cfg(feature = "slint")]
+use rt_local::{
+    runtime::slint::{run, run_event_loop_until_quit, RtLocalRuntime},
+    spawn_blocking, spawn_local, wait_for_idle, yield_now,
+};
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+// The platform can be set only once per process, so all checks are in one test.
+#[test]
+fn test_slint() {
+    // Tasks spawned before the platform is initialized are polled once the event loop starts.
+    let rt = RtLocalRuntime::new();
+    let is_polled = Rc::new(Cell::new(false));
+    spawn_local({
+        let is_polled = is_polled.clone();
+        async move {
+            is_polled.set(true);
+            slint::quit_event_loop().unwrap();
+        }
+    })
+    .detach();
+    i_slint_backend_testing::init_integration_test_with_system_time();
+    slint::run_event_loop_until_quit().unwrap();
+    assert!(is_polled.get());
+    drop(rt);
+
+    let value = run(async {
+        let start = Instant::now();
+        rt_local::time::sleep(Duration::from_millis(50)).await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let task = spawn_local(async {
+            yield_now().await;
+            wait_for_idle().await;
+            1
+        });
+        let value = spawn_blocking(|| 2).await.unwrap();
+        task.await + value
+    });
+    assert_eq!(value, 3);
+
+    slint::invoke_from_event_loop(|| {
+        spawn_local(async {
+            wait_for_idle().await;
+            slint::quit_event_loop().unwrap();
+        })
+        .detach();
+    })
+    .unwrap();
+    run_event_loop_until_quit().unwrap();
+}
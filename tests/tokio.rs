@@ -0,0 +1,45 @@
+#![cfg(feature = "tokio")]
+use rt_local::{runtime::blocking::run, spawn_local};
+use std::time::{Duration, Instant};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[test]
+fn test_tokio_sleep() {
+    run(async {
+        let start = Instant::now();
+        spawn_local(tokio::time::sleep(Duration::from_millis(50))).await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    });
+}
+
+#[test]
+fn test_tokio_net() {
+    run(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = spawn_local(async move {
+            let (mut s, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 5];
+            s.read_exact(&mut buf).await.unwrap();
+            buf
+        });
+        let mut c = TcpStream::connect(addr).await.unwrap();
+        c.write_all(b"hello").await.unwrap();
+        assert_eq!(&server.await, b"hello");
+    });
+}
+
+#[tokio::test]
+async fn test_tokio_inside_tokio_runtime() {
+    let handle = tokio::runtime::Handle::current();
+    let id = std::thread::spawn(move || {
+        let _guard = handle.enter();
+        run(async { tokio::runtime::Handle::current().id() })
+    })
+    .join()
+    .unwrap();
+    assert_eq!(id, tokio::runtime::Handle::current().id());
+}